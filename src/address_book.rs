@@ -0,0 +1,127 @@
+use namada_sdk::address::Address;
+use serde::Deserialize;
+
+use crate::get_full_path;
+
+/// Address book, relative to `NAMADA_UTILS_DIR`. Optional: without it,
+/// addresses print as they are.
+pub const ADDRESS_BOOK_FILE: &str = "config/address_book.toml";
+
+/// A labelled address, e.g.
+///
+/// ```toml
+/// [[addresses]]
+/// address = "tnam1..."
+/// label = "Treasury hot wallet"
+/// tags = ["treasury"]
+/// group = "core_team"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct AddressEntry {
+    pub address: Address,
+    pub label: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Address list the address belongs to, named after its file, e.g.
+    /// `backers` for `config/backers.txt`
+    #[serde(default)]
+    pub group: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AddressBook {
+    #[serde(default)]
+    addresses: Vec<AddressEntry>,
+}
+
+impl AddressBook {
+    pub fn parse(contents: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(contents)
+    }
+
+    /// The address book of `NAMADA_UTILS_DIR`. Empty if there is no address
+    /// book file.
+    pub fn load() -> Result<Self, toml::de::Error> {
+        match std::fs::read_to_string(get_full_path(ADDRESS_BOOK_FILE)) {
+            Ok(contents) => Self::parse(&contents),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    pub fn label(&self, address: &Address) -> Option<&str> {
+        self.addresses
+            .iter()
+            .find(|entry| &entry.address == address)
+            .map(|entry| entry.label.as_str())
+    }
+
+    /// `Label (tnam1...)`, or the bare address if it has no label
+    pub fn display(&self, address: &Address) -> String {
+        match self.label(address) {
+            Some(label) => format!("{} ({})", label, address),
+            None => address.to_string(),
+        }
+    }
+
+    /// Address of a label, ignoring case
+    pub fn find(&self, label: &str) -> Option<&Address> {
+        self.addresses
+            .iter()
+            .find(|entry| entry.label.eq_ignore_ascii_case(label))
+            .map(|entry| &entry.address)
+    }
+
+    pub fn tagged(&self, tag: &str) -> Vec<Address> {
+        self.addresses
+            .iter()
+            .filter(|entry| entry.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+            .map(|entry| entry.address.clone())
+            .collect()
+    }
+
+    pub fn group(&self, group: &str) -> Vec<Address> {
+        self.addresses
+            .iter()
+            .filter(|entry| entry.group.as_deref() == Some(group))
+            .map(|entry| entry.address.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use namada_core::address::gen_deterministic_established_address;
+
+    use super::*;
+
+    #[test]
+    fn test_address_book() {
+        let hot = gen_deterministic_established_address("hot");
+        let cold = gen_deterministic_established_address("cold");
+        let other = gen_deterministic_established_address("other");
+        let book = AddressBook::parse(&format!(
+            r#"
+            [[addresses]]
+            address = "{}"
+            label = "Treasury hot wallet"
+            tags = ["treasury", "hot"]
+
+            [[addresses]]
+            address = "{}"
+            label = "Treasury cold wallet"
+            tags = ["Treasury"]
+            group = "core_team"
+            "#,
+            hot, cold
+        ))
+        .unwrap();
+
+        assert_eq!(book.display(&hot), format!("Treasury hot wallet ({})", hot));
+        assert_eq!(book.display(&other), other.to_string());
+        assert_eq!(book.find("treasury cold wallet"), Some(&cold));
+        assert_eq!(book.tagged("treasury"), vec![hot.clone(), cold.clone()]);
+        assert_eq!(book.tagged("hot"), vec![hot]);
+        assert_eq!(book.group("core_team"), vec![cold]);
+        assert!(book.group("backers").is_empty());
+    }
+}
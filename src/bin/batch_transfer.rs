@@ -4,23 +4,53 @@ use clap::Parser;
 use namada_sdk::{
     address::Address,
     args::{InputAmount, TxBuilder, TxTransparentTransferData},
-    io::NullIo,
-    masp::fs::FsShieldedUtils,
+    key::common,
     rpc,
-    signing::default_sign,
-    wallet::fs::FsWalletUtils,
-    Namada, NamadaImpl,
+    token::Amount,
+    Namada,
 };
-use namada_utils::{build_ctx, load_keys, read_csv_to_vec};
+use namada_utils::fees::{
+    dry_run_gas_limit, estimate_gas_limit, print_fee_estimate, DEFAULT_GAS_LIMIT,
+};
+use namada_utils::multisig::{check_threshold, query_signing_account, submit_or_dump, MultisigArgs};
+use namada_utils::registry::get_token_registry;
+use namada_utils::{build_ctx, read_csv_to_vec, read_wallet_password, resolve_address, RpcArgs};
 use serde::Deserialize;
-use tendermint_rpc::HttpClient;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// Gas limit for the transaction
-    #[arg(short, long = "gas-limit", default_value_t = 50_000)]
-    gas_limit: u64,
+    #[command(flatten)]
+    rpc: RpcArgs,
+
+    /// Gas limit for the transaction. Estimated with a dry-run if not given.
+    #[arg(short, long = "gas-limit")]
+    gas_limit: Option<u64>,
+
+    /// Only dry-run the transaction and show the gas and fee estimate
+    #[arg(short, long)]
+    estimate: bool,
+
+    /// Safety margin added to the estimated gas, in percent
+    #[arg(long = "gas-margin", default_value_t = 20)]
+    gas_margin: u64,
+
+    /// Token to pay the fees in (alias or address)
+    #[arg(long = "fee-token", default_value = "nam")]
+    fee_token: String,
+
+    /// Wallet aliases of the signing keys, as imported by `import_keys`. The
+    /// first one pays the fees.
+    #[arg(short, long, value_delimiter = ',', default_value = "key-0")]
+    signers: Vec<String>,
+
+    /// Source account (alias or address), e.g. a multisig established account.
+    /// Defaults to the address of the first signer.
+    #[arg(long)]
+    source: Option<String>,
+
+    #[command(flatten)]
+    multisig: MultisigArgs,
 }
 
 #[derive(Debug, Deserialize)]
@@ -33,25 +63,17 @@ fn load_transfer_targets(path: &str) -> Vec<TransferTarget> {
     read_csv_to_vec::<TransferTarget>(path).expect("Failed to read CSV")
 }
 
-async fn build_transfer_data(
-    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
-    source: &str,
-    target: &str,
+fn build_transfer_data(
+    source: &Address,
+    target: &Address,
     token: &Address,
     raw_amount: u64,
 ) -> TxTransparentTransferData {
-    let source = sdk
-        .wallet()
-        .await
-        .find_address(source)
-        .unwrap()
-        .into_owned();
-    let target = Address::from_str(target).unwrap();
     let amount = InputAmount::from_str(raw_amount.to_string().as_str()).unwrap();
 
     TxTransparentTransferData {
-        source,
-        target,
+        source: source.clone(),
+        target: target.clone(),
         token: token.clone(),
         amount,
     }
@@ -60,57 +82,131 @@ async fn build_transfer_data(
 #[tokio::main]
 async fn main() {
     let args = Args::parse();
-    let gas_limit = args.gas_limit;
 
-    let (sdk, _config) = build_ctx().await;
+    let (sdk, config) = build_ctx(&args.rpc).await;
+    let book = &config.address_book;
 
     // Wallet things
-    load_keys(&sdk, "config/keys.csv").await;
+    let password = read_wallet_password(false);
 
     let transfer_targets = load_transfer_targets("config/transfer_targets.csv");
 
     let native_token = sdk.wallet().await.find_address("nam").unwrap().into_owned();
-    let key = sdk.wallet().await.find_public_key("key-0").unwrap();
+    let mut keys: Vec<common::PublicKey> = Vec::new();
+    for alias in &args.signers {
+        let key = sdk
+            .wallet()
+            .await
+            .find_public_key(alias)
+            .expect("Signing key not found in wallet, run import_keys first");
+        keys.push(key);
+    }
+    let source = resolve_address(
+        &*sdk.wallet().await,
+        book,
+        args.source.as_ref().unwrap_or(&args.signers[0]),
+    );
 
     let token = native_token;
-
+    let fee_token = resolve_address(&*sdk.wallet().await, book, &args.fee_token);
+    let registry = get_token_registry(&*sdk.wallet().await, &config);
+
+    // Check the source threshold before doing anything else
+    let account = query_signing_account(&sdk, &source).await;
+    let threshold_met = check_threshold(
+        &format!("Source {}", book.display(&source)),
+        &account,
+        &keys,
+        &args.multisig.signature_files(),
+    );
+
+    // Targets may be addresses, address book labels or wallet aliases
+    let targets: Vec<Address> = {
+        let wallet = sdk.wallet().await;
+        transfer_targets
+            .iter()
+            .map(|target| resolve_address(&wallet, book, &target.address))
+            .collect()
+    };
     let mut data = Vec::new();
-    for target in &transfer_targets {
-        data.push(build_transfer_data(&sdk, "key-0", &target.address, &token, target.amount).await);
+    for (target, address) in transfer_targets.iter().zip(&targets) {
+        data.push(build_transfer_data(&source, address, &token, target.amount));
+    }
+
+    let new_transfer_tx_builder = |gas_limit: u64| {
+        sdk.new_transparent_transfer(data.clone())
+            .signing_keys(keys.clone())
+            .gas_limit(gas_limit.into())
+            .fee_token(fee_token.clone())
+            .password(password.clone())
+    };
+
+    // The gas limit of a dumped tx is already fixed in its wrapper header
+    let mut gas_limit = args.gas_limit.unwrap_or(DEFAULT_GAS_LIMIT);
+    if args.multisig.tx_file.is_none() && (args.estimate || args.gas_limit.is_none()) {
+        if threshold_met {
+            let sent = transfer_targets.iter().fold(Amount::zero(), |acc, target| {
+                acc.checked_add(Amount::native_whole(target.amount)).unwrap()
+            });
+            let dry_run_limit =
+                dry_run_gas_limit(&sdk, &keys[0], &fee_token, &source, &token, sent).await;
+            let mut dry_run_builder = new_transfer_tx_builder(dry_run_limit);
+            let (dry_run_tx, signing_data) = dry_run_builder
+                .build(&sdk)
+                .await
+                .expect("unable to build transfer");
+            let (gas_used, estimated_limit) = estimate_gas_limit(
+                &sdk,
+                dry_run_tx,
+                &dry_run_builder.tx,
+                signing_data,
+                &[],
+                args.gas_margin,
+            )
+            .await
+            .expect("Dry-run of the transfer failed, check the targets or pass --gas-limit");
+            gas_limit = estimated_limit;
+            print_fee_estimate(&sdk, &registry, &fee_token, gas_used, gas_limit).await;
+        } else {
+            println!(
+                "Not enough signatures to dry-run the transfer, using a gas limit of {}",
+                gas_limit
+            );
+        }
+        if args.estimate {
+            return;
+        }
     }
 
-    let mut transfer_tx_builder = sdk
-        .new_transparent_transfer(data)
-        .signing_keys(vec![key.clone()])
-        .gas_limit(gas_limit.into())
-        .fee_token(token.clone());
+    let mut transfer_tx_builder = new_transfer_tx_builder(gas_limit);
 
-    let (mut transfer_tx, signing_data) = transfer_tx_builder
-        .build(&sdk)
-        .await
-        .expect("unable to build transfer");
+    let (transfer_tx, signing_data) = match args.multisig.dumped_tx(&account, &keys) {
+        Some(dumped) => dumped,
+        None => transfer_tx_builder
+            .build(&sdk)
+            .await
+            .expect("unable to build transfer"),
+    };
 
-    sdk.sign(
-        &mut transfer_tx,
+    let response = submit_or_dump(
+        &sdk,
+        transfer_tx,
         &transfer_tx_builder.tx,
         signing_data,
-        default_sign,
-        (),
+        threshold_met,
+        &args.multisig,
+        "",
     )
-    .await
-    .expect("unable to sign transparent-transfer tx");
-
-    match sdk.submit(transfer_tx, &transfer_tx_builder.tx).await {
-        Ok(res) => println!("Tx result: {:?}", res),
-        Err(e) => println!("\n\nTx error: {:?}\n\n", e),
+    .await;
+    if response.is_none() {
+        return;
     }
 
     // Print some results out
-    for target in transfer_targets {
-        let target = Address::from_str(&target.address).unwrap();
+    for target in targets {
         let balance = rpc::get_token_balance(&sdk.client, &token, &target, None)
             .await
             .unwrap();
-        println!("{}:  {}", &target, balance.to_string_native());
+        println!("{}:  {}", book.display(&target), balance.to_string_native());
     }
 }
@@ -1,9 +1,66 @@
 use std::str::FromStr;
+use std::time::Duration;
 
+use clap::Parser;
+use namada_core::address::Address;
+use namada_core::chain::Epoch;
 use namada_proof_of_stake::rewards::PosRewardsRates;
-use namada_sdk::{rpc, state::LastBlock, time::DateTimeUtc, Namada};
-use namada_token::Dec;
-use namada_utils::build_ctx;
+use namada_sdk::io::NullIo;
+use namada_sdk::masp::fs::FsShieldedUtils;
+use namada_sdk::tendermint_rpc::HttpClient;
+use namada_sdk::wallet::fs::FsWalletUtils;
+use namada_sdk::{rpc, state::LastBlock, time::DateTimeUtc, Namada, NamadaImpl};
+use namada_token::{Amount, Dec};
+use namada_utils::epoch::{
+    query_epoch_schedule, query_last_block, sample_block_times, EpochSchedule,
+};
+use namada_utils::hooks::{Alert, Hooks};
+use namada_utils::{build_report_ctx, CacheArgs, RpcArgs};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    rpc: RpcArgs,
+
+    #[command(flatten)]
+    cache: CacheArgs,
+
+    /// Number of upcoming epochs to predict the start of
+    #[arg(long, default_value_t = 1)]
+    epochs: u64,
+
+    /// Number of recent blocks to measure the block time over
+    #[arg(
+        long = "sample-blocks",
+        default_value_t = 100,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    sample_blocks: u64,
+
+    /// Keep refreshing the height, epoch, countdown, staked ratio and
+    /// balances
+    #[arg(long)]
+    watch: bool,
+
+    /// Seconds between refreshes in watch mode
+    #[arg(long, default_value_t = 30)]
+    interval: u64,
+
+    /// Alert when the staked ratio moves by more than this many percentage
+    /// points since the last alert
+    #[arg(long = "ratio-threshold", default_value = "1")]
+    ratio_threshold: String,
+
+    /// Shell command run on alerts, with the alert in ALERT_KIND,
+    /// ALERT_MESSAGE, ALERT_HEIGHT, ALERT_EPOCH and ALERT_STAKED_RATIO
+    #[arg(long = "hook-cmd")]
+    hook_cmd: Option<String>,
+
+    /// URL the alerts are POSTed to as JSON
+    #[arg(long = "hook-url")]
+    hook_url: Option<String>,
+}
 
 fn convert_to_hours(seconds: u64) -> String {
     let hours = seconds / 3600;
@@ -21,7 +78,13 @@ fn convert_to_hours(seconds: u64) -> String {
 
 #[tokio::main]
 async fn main() {
-    let (sdk, _config) = build_ctx().await;
+    let args = Args::parse();
+    let (sdk, _config) = build_report_ctx(&args.rpc, &args.cache).await;
+
+    if args.watch {
+        watch(&sdk, &args).await;
+        return;
+    }
 
     let native_token = sdk.wallet().await.find_address("nam").unwrap().into_owned();
 
@@ -39,43 +102,69 @@ async fn main() {
         .expect("Query epoch error");
     println!("Current epoch: {}\n", current_epoch);
 
-    let (first_height_current_epoch, epoch_duration) = rpc::query_next_epoch_info(&sdk.client)
+    let schedule = query_epoch_schedule(&sdk).await.unwrap();
+    let last_block = query_last_block(&sdk).await.unwrap();
+    let last_height = last_block.height.0;
+    let last_time = last_block.time.to_unix_timestamp();
+    let block_times = sample_block_times(&sdk, last_height, args.sample_blocks)
         .await
-        .expect("Query next epoch info error");
-
-    let first_header_current_epoch =
-        rpc::query_block_header(&sdk.client, first_height_current_epoch)
-            .await
-            .unwrap()
-            .unwrap();
-
-    let first_block_time = first_header_current_epoch.time;
-    let next_epoch_time = first_block_time + epoch_duration.min_duration;
-
-    #[allow(clippy::disallowed_methods)]
-    let current_time = DateTimeUtc::now();
-    let seconds_left = next_epoch_time.time_diff(current_time).0;
-    let time_remaining_str = convert_to_hours(seconds_left);
+        .unwrap();
 
     println!(
-        "First block height of epoch {current_epoch}: \
-         {first_height_current_epoch}.\n"
+        "First block height of epoch {current_epoch}: {}.\n",
+        schedule.first_height
     );
     println!(
         "Minimum number of blocks in an epoch: {}.",
-        epoch_duration.min_num_of_blocks
+        schedule.min_num_of_blocks
     );
     println!(
         "Minimum amount of time for an epoch: {}.",
-        convert_to_hours(epoch_duration.min_duration.0)
+        convert_to_hours(schedule.min_duration as u64)
     );
     println!(
-        "\nNext epoch ({}) begins in {} or at block height {}, whichever \
-         occurs later.\n",
-        current_epoch.next(),
-        time_remaining_str,
-        first_height_current_epoch.0 + epoch_duration.min_num_of_blocks + 2
+        "Average block time over the last {} blocks: {:.2}s (std dev {:.2}s).",
+        block_times.samples, block_times.mean, block_times.std_dev
     );
+    match (
+        schedule.time_condition_met(last_time),
+        schedule.block_condition_met(last_height),
+    ) {
+        (true, true) => {
+            println!("Both epoch conditions are met, the next epoch is about to begin.")
+        }
+        (true, false) => println!(
+            "The minimum epoch duration has passed, the next epoch waits for block height {}.",
+            schedule.min_start_height()
+        ),
+        (false, true) => println!(
+            "The minimum number of blocks is reached, the next epoch waits for {}.",
+            DateTimeUtc::from_unix_timestamp(schedule.min_start_time()).unwrap()
+        ),
+        (false, false) => {}
+    }
+
+    let now = DateTimeUtc::now().to_unix_timestamp();
+    for prediction in schedule.predict(last_height, last_time, &block_times, args.epochs) {
+        let time = DateTimeUtc::from_unix_timestamp(prediction.time).unwrap();
+        let earliest = DateTimeUtc::from_unix_timestamp(prediction.time_range.0).unwrap();
+        let latest = DateTimeUtc::from_unix_timestamp(prediction.time_range.1).unwrap();
+        println!(
+            "\nEpoch {} begins at block height {} (between {} and {}),",
+            prediction.epoch,
+            prediction.height,
+            prediction.height_range.0,
+            prediction.height_range.1
+        );
+        println!(
+            "in {} at {} (between {} and {}).",
+            convert_to_hours((prediction.time - now).max(0) as u64),
+            time,
+            earliest,
+            latest
+        );
+    }
+    println!();
 
     println!("\n---------- Staking rewards -------------\n");
     match rpc::get_staking_rewards_rate(&sdk.client).await {
@@ -96,7 +185,12 @@ async fn main() {
 
     println!("\n---------- Balances -------------\n");
     let pgf_address = sdk.wallet().await.find_address("pgf").unwrap().into_owned();
-    let gov_address = sdk.wallet().await.find_address("governance").unwrap().into_owned();
+    let gov_address = sdk
+        .wallet()
+        .await
+        .find_address("governance")
+        .unwrap()
+        .into_owned();
     let pgf_balance = rpc::get_token_balance(&sdk.client, &native_token, &pgf_address, None)
         .await
         .unwrap();
@@ -138,3 +232,181 @@ async fn main() {
         total_staked_tokens_pipeline.to_string_native()
     );
 }
+
+/// Chain state redrawn in watch mode
+struct WatchState {
+    schedule: EpochSchedule,
+    last_block: LastBlock,
+    staked_ratio: Dec,
+    pgf_balance: Amount,
+    gov_balance: Amount,
+}
+
+async fn query_watch_state(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    pgf_address: &Address,
+    gov_address: &Address,
+) -> Result<WatchState, String> {
+    let schedule = query_epoch_schedule(sdk).await?;
+    let last_block = query_last_block(sdk).await?;
+    let native_token = sdk.native_token();
+
+    let total_staked = rpc::get_total_staked_tokens(&sdk.client, schedule.epoch)
+        .await
+        .map_err(|e| format!("Query total stake error: {}", e))?;
+    let native_supply = rpc::get_effective_native_supply(&sdk.client)
+        .await
+        .map_err(|e| format!("Query native supply error: {}", e))?;
+    let staked_ratio = Dec::try_from(total_staked)
+        .unwrap()
+        .checked_div(Dec::try_from(native_supply).unwrap())
+        .unwrap();
+    let pgf_balance = rpc::get_token_balance(&sdk.client, &native_token, pgf_address, None)
+        .await
+        .map_err(|e| format!("Query PGF balance error: {}", e))?;
+    let gov_balance = rpc::get_token_balance(&sdk.client, &native_token, gov_address, None)
+        .await
+        .map_err(|e| format!("Query governance balance error: {}", e))?;
+
+    Ok(WatchState {
+        schedule,
+        last_block,
+        staked_ratio,
+        pgf_balance,
+        gov_balance,
+    })
+}
+
+/// Report a failed refresh and wait for the next one
+async fn retry_later(error: String, interval: u64) {
+    eprintln!("WARNING: {}, retrying in {}s", error, interval);
+    tokio::time::sleep(Duration::from_secs(interval)).await;
+}
+
+/// Redraw the chain state every `--interval` seconds and run the hooks when
+/// the epoch changes or the staked ratio moves past the threshold
+async fn watch(sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>, args: &Args) {
+    let hooks = Hooks {
+        command: args.hook_cmd.clone(),
+        url: args.hook_url.clone(),
+    };
+    let threshold = Dec::from_str(&args.ratio_threshold)
+        .expect("Invalid staked ratio threshold")
+        .checked_div(Dec::from(100u64))
+        .unwrap();
+
+    let pgf_address = sdk.wallet().await.find_address("pgf").unwrap().into_owned();
+    let gov_address = sdk
+        .wallet()
+        .await
+        .find_address("governance")
+        .unwrap()
+        .into_owned();
+
+    let mut last_epoch: Option<Epoch> = None;
+    let mut block_times = None;
+    // Staked ratio when the last ratio alert was sent
+    let mut alert_ratio: Option<Dec> = None;
+    let mut alerts: Vec<String> = Vec::new();
+
+    loop {
+        let WatchState {
+            schedule,
+            last_block,
+            staked_ratio,
+            pgf_balance,
+            gov_balance,
+        } = match query_watch_state(sdk, &pgf_address, &gov_address).await {
+            Ok(state) => state,
+            Err(e) => {
+                retry_later(e, args.interval).await;
+                continue;
+            }
+        };
+        let last_height = last_block.height.0;
+        let last_time = last_block.time.to_unix_timestamp();
+        // Block times barely move within an epoch
+        if block_times.is_none() || last_epoch != Some(schedule.epoch) {
+            match sample_block_times(sdk, last_height, args.sample_blocks).await {
+                Ok(times) => block_times = Some(times),
+                Err(e) => {
+                    retry_later(e, args.interval).await;
+                    continue;
+                }
+            }
+        }
+        let block_times = block_times.as_ref().unwrap();
+
+        let mut new_alerts = Vec::new();
+        if let Some(epoch) = last_epoch.filter(|epoch| *epoch != schedule.epoch) {
+            new_alerts.push((
+                "epoch_change",
+                format!("Epoch changed from {} to {}", epoch, schedule.epoch),
+            ));
+        }
+        match alert_ratio {
+            Some(ratio) if staked_ratio.abs_diff(ratio).unwrap() > threshold => {
+                new_alerts.push((
+                    "staked_ratio",
+                    format!(
+                        "Staked ratio moved from {}% to {}%",
+                        ratio.checked_mul(Dec::from(100u64)).unwrap(),
+                        staked_ratio.checked_mul(Dec::from(100u64)).unwrap()
+                    ),
+                ));
+                alert_ratio = Some(staked_ratio);
+            }
+            Some(_) => {}
+            None => alert_ratio = Some(staked_ratio),
+        }
+        last_epoch = Some(schedule.epoch);
+
+        for (kind, message) in new_alerts {
+            let alert = Alert {
+                kind: kind.to_string(),
+                message: message.clone(),
+                height: last_height,
+                epoch: schedule.epoch.0,
+                staked_ratio: staked_ratio.to_string(),
+            };
+            hooks.notify(&alert).await;
+            alerts.push(format!(
+                "{} (height {}): {}",
+                last_block.time, last_height, message
+            ));
+        }
+
+        let now = DateTimeUtc::now().to_unix_timestamp();
+        let next_epoch = &schedule.predict(last_height, last_time, block_times, 1)[0];
+
+        // Clear the screen and redraw
+        print!("\x1B[2J\x1B[H");
+        println!(
+            "Last block height: {} - (time: {})",
+            last_height, last_block.time
+        );
+        println!("Current epoch: {}", schedule.epoch);
+        println!(
+            "Next epoch ({}) begins in {} at block height {} (between {} and {})",
+            next_epoch.epoch,
+            convert_to_hours((next_epoch.time - now).max(0) as u64),
+            next_epoch.height,
+            next_epoch.height_range.0,
+            next_epoch.height_range.1
+        );
+        println!(
+            "Staked ratio: {}%",
+            staked_ratio.checked_mul(Dec::from(100u64)).unwrap()
+        );
+        println!("PGF balance: {} NAM", pgf_balance.to_string_native());
+        println!("Gov balance: {} NAM", gov_balance.to_string_native());
+        if !alerts.is_empty() {
+            println!("\nRecent alerts:");
+            for alert in alerts.iter().rev().take(5) {
+                println!("  {}", alert);
+            }
+        }
+
+        tokio::time::sleep(Duration::from_secs(args.interval)).await;
+    }
+}
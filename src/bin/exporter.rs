@@ -0,0 +1,84 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use clap::Parser;
+use namada_sdk::Namada;
+use namada_utils::metrics::Metrics;
+use namada_utils::registry::get_token_registry;
+use namada_utils::{build_report_ctx, CacheArgs, RpcArgs};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::RwLock;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    rpc: RpcArgs,
+
+    #[command(flatten)]
+    cache: CacheArgs,
+
+    /// Address the /metrics endpoint listens on
+    #[arg(long, default_value = "0.0.0.0:9184")]
+    listen: String,
+
+    /// Seconds between refreshes of the metrics
+    #[arg(long, default_value_t = 30)]
+    interval: u64,
+}
+
+/// Answer a single HTTP request with the last rendered metrics
+async fn serve(mut stream: TcpStream, rendered: Arc<RwLock<String>>) {
+    let mut buf = [0u8; 1024];
+    let Ok(read) = stream.read(&mut buf).await else {
+        return;
+    };
+    let request = String::from_utf8_lossy(&buf[..read]);
+    let path = request.split_whitespace().nth(1).unwrap_or_default();
+
+    let response = if request.starts_with("GET ") && path == "/metrics" {
+        let body = rendered.read().await;
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    } else {
+        String::from("HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+    };
+    let _ = stream.write_all(response.as_bytes()).await;
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    let (sdk, config) = build_report_ctx(&args.rpc, &args.cache).await;
+    let registry = get_token_registry(&*sdk.wallet().await, &config);
+
+    let mut metrics = Metrics::new(&config.chain_id);
+    metrics.refresh(&sdk, &registry).await;
+    let rendered = Arc::new(RwLock::new(metrics.render()));
+
+    let listener = TcpListener::bind(&args.listen)
+        .await
+        .expect("Could not bind the metrics address");
+    println!("Serving metrics on http://{}/metrics", args.listen);
+    let server_rendered = rendered.clone();
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(serve(stream, server_rendered.clone()));
+                }
+                Err(e) => println!("Accept error: {}", e),
+            }
+        }
+    });
+
+    loop {
+        tokio::time::sleep(Duration::from_secs(args.interval)).await;
+        metrics.refresh(&sdk, &registry).await;
+        *rendered.write().await = metrics.render();
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use clap::{Parser, Subcommand};
+use namada_core::token;
+use namada_utils::address_book::AddressBook;
+use namada_utils::genesis::{
+    categorize, known_categories, parse_genesis, CategorySource, CATEGORIES,
+};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Write the genesis accounts of each allocation category from Namada's
+    /// genesis `balances.toml` and `transactions.toml`
+    Import {
+        /// Genesis balances file
+        #[arg(long, default_value = "balances.toml")]
+        balances: PathBuf,
+
+        /// Genesis transactions file, for the validators and genesis bonds
+        #[arg(long, default_value = "transactions.toml")]
+        transactions: PathBuf,
+
+        /// Alias of the native token in the balances file
+        #[arg(long, default_value = "NAM")]
+        token: String,
+
+        /// Directory to write the category files to
+        #[arg(short, long, default_value = "genesis-categories")]
+        output: PathBuf,
+    },
+}
+
+fn sum(amounts: impl Iterator<Item = token::Amount>) -> token::Amount {
+    amounts.fold(token::Amount::zero(), |total, amount| {
+        total.checked_add(amount).unwrap()
+    })
+}
+
+/// Compare an imported total with the one the crate uses
+fn check(imported: token::Amount, expected: token::Amount) {
+    if imported == expected {
+        println!(
+            "Matches the {} NAM the crate uses",
+            expected.to_string_native()
+        );
+        return;
+    }
+    let difference = match imported.checked_sub(expected) {
+        Some(more) => format!("+{}", more.to_string_native()),
+        None => format!(
+            "-{}",
+            expected.checked_sub(imported).unwrap().to_string_native()
+        ),
+    };
+    println!(
+        "WARNING: the crate uses {} NAM, a difference of {} NAM",
+        expected.to_string_native(),
+        difference
+    );
+}
+
+fn write_json<T: serde::Serialize>(path: &Path, value: &T) {
+    let file = std::fs::File::create(path).expect("Could not create a category file");
+    serde_json::to_writer_pretty(file, value).expect("Could not write a category file");
+}
+
+fn import(balances: &Path, transactions: &Path, token: &str, output: &Path) {
+    let balances = std::fs::read_to_string(balances).expect("Could not read the balances file");
+    let transactions =
+        std::fs::read_to_string(transactions).expect("Could not read the transactions file");
+    let accounts =
+        parse_genesis(&balances, &transactions, token).unwrap_or_else(|e| panic!("{}", e));
+    let bonded: BTreeMap<_, _> = accounts
+        .iter()
+        .map(|account| (&account.address, account.bonded))
+        .collect();
+    println!(
+        "Genesis accounts with a {} balance: {}",
+        token,
+        accounts.len()
+    );
+
+    let book = AddressBook::load().expect("Could not parse the address book");
+    let known = known_categories(&book);
+    let records = categorize(&accounts, &known);
+    std::fs::create_dir_all(output).expect("Could not create the output directory");
+
+    for category in &CATEGORIES {
+        let records = records
+            .get(category.key)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let balance = sum(records.iter().map(|record| record.amount));
+        println!("\n--- {} --------", category.title);
+        println!("Accounts: {}", records.len());
+        println!("Genesis balance: {} NAM", balance.to_string_native());
+        check(balance, (category.genesis_balance)());
+        println!(
+            "Bonded at genesis: {} NAM",
+            sum(records.iter().map(|record| bonded[&record.address])).to_string_native()
+        );
+        for (address, _) in known
+            .iter()
+            .filter(|(address, (key, _))| *key == category.key && !bonded.contains_key(address))
+        {
+            println!(
+                "WARNING: {} is listed in this category but has no genesis balance",
+                book.display(address)
+            );
+        }
+
+        let path = output.join(format!("{}.json", category.file_stem()));
+        write_json(&path, &records);
+        println!("Written to {}", path.display());
+        if let CategorySource::List(_) = category.source {
+            let path = output.join(format!("{}.txt", category.file_stem()));
+            let list: String = records
+                .iter()
+                .map(|record| format!("{}\n", record.address))
+                .collect();
+            std::fs::write(&path, list).expect("Could not write a category file");
+            println!("Written to {}", path.display());
+        }
+    }
+
+    println!("\n--- Total --------");
+    let total = sum(accounts.iter().map(|account| account.balance));
+    println!("Genesis balance: {} NAM", total.to_string_native());
+    check(total, token::Amount::native_whole(1_000_000_000));
+}
+
+fn main() {
+    let args = Args::parse();
+    match args.command {
+        Command::Import {
+            balances,
+            transactions,
+            token,
+            output,
+        } => import(&balances, &transactions, &token, &output),
+    }
+}
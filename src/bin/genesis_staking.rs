@@ -1,22 +1,33 @@
 use std::{fs::File, str::FromStr};
 
+use clap::Parser;
 use csv::Writer;
 use namada_core::token;
 use namada_sdk::{
-    address::Address,
     collections::HashMap,
     rpc::{self, enriched_bonds_and_unbonds},
 };
 use namada_token::Dec;
 use namada_utils::{
-    build_ctx, get_addresses_from_file, get_backer_balance, get_bonds_to_top_validators,
-    get_core_balance, get_future_alloc_balance, get_genesis_accounts, get_pg_validator_balance,
-    get_public_alloc_balance, get_rd_balance, Record,
+    build_report_ctx, get_bonds_to_top_validators, get_genesis_accounts, get_genesis_categories,
+    get_pg_validator_balance, get_public_alloc_balance, get_public_alloc_stake, CacheArgs, Record,
+    RpcArgs,
 };
 
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    rpc: RpcArgs,
+
+    #[command(flatten)]
+    cache: CacheArgs,
+}
+
 #[tokio::main]
 async fn main() {
-    let (sdk, _config) = build_ctx().await;
+    let args = Args::parse();
+    let (sdk, config) = build_report_ctx(&args.rpc, &args.cache).await;
 
     let current_epoch = rpc::query_epoch(&sdk.client)
         .await
@@ -28,8 +39,9 @@ async fn main() {
         .unwrap();
     println!("Total stake: {} NAM", total_stake.to_string_native());
     let total_staked_dec = Dec::try_from(total_stake).unwrap();
+    let [backers, core_team, rd_ecosystems, future_allocs] = get_genesis_categories();
 
-    println!("\n---------- Backers --------------------------\n");
+    println!("\n---------- {} --------------------------\n", backers.name);
     let mut backer_stake = token::Amount::zero();
     let mut backer_bonds_to_top_vals = HashMap::<String, token::Amount>::new();
 
@@ -37,6 +49,7 @@ async fn main() {
     let mut wtr = Writer::from_writer(file);
     wtr.write_record([
         "Address",
+        "Label",
         "Total Stake",
         "Unit 410 [1]",
         "Unit 410 [2]",
@@ -62,8 +75,7 @@ async fn main() {
             }
         };
 
-    let sources = get_addresses_from_file("config/backers.txt");
-    for delegator in sources {
+    for delegator in backers.delegators(&config.address_book) {
         let bonds =
             enriched_bonds_and_unbonds(&sdk.client, current_epoch, &Some(delegator.clone()), &None)
                 .await
@@ -85,6 +97,11 @@ async fn main() {
 
         wtr.write_record(&[
             delegator.to_string(),
+            config
+                .address_book
+                .label(&delegator)
+                .unwrap_or_default()
+                .to_string(),
             bonded.to_string_native(),
             u410_frac1.to_string(),
             u410_frac2.to_string(),
@@ -95,11 +112,11 @@ async fn main() {
         .unwrap();
     }
     let backer_staked_dec = Dec::try_from(backer_stake).unwrap();
-    let backer_balance_dec = Dec::try_from(get_backer_balance()).unwrap();
+    let backer_balance_dec = Dec::try_from(backers.genesis_balance).unwrap();
     let backer_frac = backer_staked_dec.checked_div(total_staked_dec).unwrap();
     println!(
         "Genesis balance: {} NAM\n",
-        get_backer_balance().to_string_native()
+        backers.genesis_balance.to_string_native()
     );
     println!(
         "Backer fraction of total stake: {}%\nFraction of backer tokens staked: {}%",
@@ -144,11 +161,11 @@ async fn main() {
     wtr.flush().unwrap();
     println!("\nData written to output.csv\n");
 
-    println!("\n---------- Core team --------------------------\n");
+    println!("\n---------- {} --------------------------\n", core_team.name);
     let mut core_stake = token::Amount::zero();
     let mut core_team_bonds_to_top_vals = HashMap::<String, token::Amount>::new();
 
-    for delegator in get_addresses_from_file("config/core_team.txt") {
+    for delegator in core_team.delegators(&config.address_book) {
         let bonds =
             enriched_bonds_and_unbonds(&sdk.client, current_epoch, &Some(delegator.clone()), &None)
                 .await
@@ -163,11 +180,11 @@ async fn main() {
         }
     }
     let core_staked_dec = Dec::try_from(core_stake).unwrap();
-    let core_balance_dec = Dec::try_from(get_core_balance()).unwrap();
+    let core_balance_dec = Dec::try_from(core_team.genesis_balance).unwrap();
     let core_frac = core_staked_dec.checked_div(total_staked_dec).unwrap();
     println!(
         "Genesis balance: {} NAM\n",
-        get_core_balance().to_string_native()
+        core_team.genesis_balance.to_string_native()
     );
     println!(
         "Core team fraction of total stake: {}%\nFraction of core team tokens staked: {}%",
@@ -198,19 +215,18 @@ async fn main() {
             .unwrap()
     );
 
-    println!("\n---------- R&D ecosystems -------------\n");
+    println!("\n---------- {} --------------------------\n", rd_ecosystems.name);
     let mut rd_stake = token::Amount::zero();
     let mut gen_balances = HashMap::<String, token::Amount>::new();
     let mut stakes = HashMap::<String, token::Amount>::new();
 
     for Record {
-        address,
+        address: delegator,
         amount,
         category: _,
         name,
-    } in get_genesis_accounts("config/rd_ecosystem_dev.json")
+    } in get_genesis_accounts(&config.address_book, rd_ecosystems.file)
     {
-        let delegator = Address::from_str(&address).unwrap();
         let bonds =
             enriched_bonds_and_unbonds(&sdk.client, current_epoch, &Some(delegator.clone()), &None)
                 .await
@@ -218,7 +234,7 @@ async fn main() {
         let bonded = bonds.bonds_total_active().unwrap();
         rd_stake = rd_stake.checked_add(bonded).unwrap();
 
-        let gen_balance = token::Amount::from(amount);
+        let gen_balance = amount;
         let a = gen_balances.entry(name.clone()).or_default();
         *a = a.checked_add(gen_balance).unwrap();
 
@@ -226,11 +242,11 @@ async fn main() {
         *b = b.checked_add(bonded).unwrap();
     }
     let rd_staked_dec = Dec::try_from(rd_stake).unwrap();
-    let rd_balance_dec = Dec::try_from(get_rd_balance()).unwrap();
+    let rd_balance_dec = Dec::try_from(rd_ecosystems.genesis_balance).unwrap();
     let rd_frac = rd_staked_dec.checked_div(total_staked_dec).unwrap();
     println!(
         "Genesis balance: {} NAM",
-        get_rd_balance().to_string_native()
+        rd_ecosystems.genesis_balance.to_string_native()
     );
     for (name, balance) in gen_balances.iter() {
         println!("  --> {}: {}", name, balance.to_string_native(),);
@@ -262,17 +278,16 @@ async fn main() {
         );
     }
 
-    println!("\n---------- Future allocations --------------------------\n");
+    println!("\n---------- {} --------------------------\n", future_allocs.name);
     let mut future_alloc_stake = token::Amount::zero();
     let mut gen_balances = HashMap::<String, token::Amount>::new();
     for Record {
-        address,
+        address: delegator,
         amount,
         category: _,
         name,
-    } in get_genesis_accounts("config/public_allocations_future.json")
+    } in get_genesis_accounts(&config.address_book, future_allocs.file)
     {
-        let delegator = Address::from_str(&address).unwrap();
         let bonds =
             enriched_bonds_and_unbonds(&sdk.client, current_epoch, &Some(delegator.clone()), &None)
                 .await
@@ -281,7 +296,7 @@ async fn main() {
         future_alloc_stake = future_alloc_stake.checked_add(bonded).unwrap();
 
         let a = gen_balances.entry(name.clone()).or_default();
-        *a = a.checked_add(token::Amount::from(amount)).unwrap();
+        *a = a.checked_add(amount).unwrap();
     }
 
     let future_alloc_stake_dec = Dec::try_from(future_alloc_stake).unwrap();
@@ -290,7 +305,7 @@ async fn main() {
         .unwrap();
     println!(
         "Genesis balance: {} NAM",
-        get_future_alloc_balance().to_string_native()
+        future_allocs.genesis_balance.to_string_native()
     );
     for (name, balance) in gen_balances.iter() {
         println!("  --> {}: {}", name, balance.to_string_native());
@@ -307,22 +322,20 @@ async fn main() {
         "Genesis balance: {}",
         get_public_alloc_balance().to_string_native()
     );
-    let rem_frac = Dec::one()
-        .checked_sub(backer_frac)
-        .unwrap()
-        .checked_sub(core_frac)
-        .unwrap()
-        .checked_sub(rd_frac)
+    let public_stake = get_public_alloc_stake(
+        total_stake,
+        [backer_stake, core_stake, rd_stake, future_alloc_stake],
+    );
+    let rem_frac = Dec::try_from(public_stake)
         .unwrap()
-        .checked_sub(future_alloc_frac)
+        .checked_div(total_staked_dec)
         .unwrap();
     println!(
         "\nAssumed public allocations fraction of total stake: {}%",
         rem_frac.checked_mul(Dec::from_str("100").unwrap()).unwrap()
     );
 
-    let frac_pub_staked = rem_frac
-        .checked_mul(total_staked_dec)
+    let frac_pub_staked = Dec::try_from(public_stake)
         .unwrap()
         .checked_div(Dec::try_from(get_public_alloc_balance()).unwrap())
         .unwrap();
@@ -334,13 +347,13 @@ async fn main() {
     );
 
     let rem_tokens = token::Amount::native_whole(1_000_000_000)
-        .checked_sub(get_backer_balance())
+        .checked_sub(backers.genesis_balance)
         .unwrap()
-        .checked_sub(get_core_balance())
+        .checked_sub(core_team.genesis_balance)
         .unwrap()
-        .checked_sub(get_rd_balance())
+        .checked_sub(rd_ecosystems.genesis_balance)
         .unwrap()
-        .checked_sub(get_future_alloc_balance())
+        .checked_sub(future_allocs.genesis_balance)
         .unwrap()
         .checked_sub(get_public_alloc_balance())
         .unwrap();
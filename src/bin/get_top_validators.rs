@@ -3,12 +3,18 @@ use std::str::FromStr;
 use clap::Parser;
 use namada_sdk::rpc;
 use namada_token::Dec;
-use namada_utils::build_ctx;
+use namada_utils::{build_report_ctx, query_top_validators, CacheArgs, RpcArgs};
 
 /// Argument parsing
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
+    #[command(flatten)]
+    rpc: RpcArgs,
+
+    #[command(flatten)]
+    cache: CacheArgs,
+
     /// Number of top validators to display
     #[arg(short, long = "num-vals")]
     num_vals: Option<u8>,
@@ -17,7 +23,7 @@ struct Args {
 #[tokio::main]
 async fn main() {
     let args = Args::parse();
-    let (sdk, _config) = build_ctx().await;
+    let (sdk, config) = build_report_ctx(&args.rpc, &args.cache).await;
 
     let num_vals = args.num_vals.unwrap_or(25);
 
@@ -26,14 +32,6 @@ async fn main() {
         .expect("Query epoch error");
     println!("Current epoch: {}\n", current_epoch);
 
-    let mut consensus_validators = rpc::get_all_consensus_validators(&sdk.client, current_epoch)
-        .await
-        .expect("Query consensus validators error")
-        .into_iter()
-        .collect::<Vec<_>>();
-
-    consensus_validators.sort_by(|a, b| b.bonded_stake.cmp(&a.bonded_stake));
-
     let total_stake = rpc::get_total_staked_tokens(&sdk.client, current_epoch)
         .await
         .unwrap();
@@ -41,33 +39,20 @@ async fn main() {
 
     println!("Top {} validators by stake (with cumulative VP):", num_vals);
     let mut cumulative_stake_frac = Dec::zero();
-    for (i, val) in consensus_validators.iter().enumerate() {
-        if i >= num_vals as usize {
-            break;
-        }
-        let stake_frac = Dec::try_from(val.bonded_stake)
+    let top_validators =
+        query_top_validators(&sdk, &config.address_book, current_epoch, num_vals as usize)
+            .await
+            .unwrap();
+    for val in top_validators {
+        let stake_frac = Dec::try_from(val.stake)
             .unwrap()
             .checked_div(total_stake)
             .unwrap();
         cumulative_stake_frac = cumulative_stake_frac.checked_add(stake_frac).unwrap();
 
-        let val_metadata = rpc::query_metadata(&sdk.client, &val.address, Some(current_epoch))
-            .await
-            .unwrap()
-            .0
-            .unwrap();
-
-        let name = if let Some(name) = val_metadata.name {
-            name
-        } else {
-            String::from("None")
-        };
-
-        // let discord = if let Some(discord) = val_metadata.discord_handle {
-        //     discord
-        // } else {
-        //     String::from("None")
-        // };
+        let name = val
+            .name
+            .unwrap_or_else(|| config.address_book.display(&val.address));
 
         let stake_frac = stake_frac
             .checked_mul(Dec::from_str("100").unwrap())
@@ -81,8 +66,7 @@ async fn main() {
             "{}% ({}%) --- {}",
             &stake_frac.to_string()[..5],
             &cumulative_stake_frac.to_string()[..5],
-            name //, discord,
-                 // val_metadata.email
+            name
         );
     }
 }
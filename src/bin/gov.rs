@@ -0,0 +1,474 @@
+use std::path::PathBuf;
+
+use clap::{Args as ClapArgs, Parser, Subcommand};
+use namada_core::chain::Epoch;
+use namada_governance::utils::TallyType;
+use namada_sdk::address::Address;
+use namada_sdk::args::TxBuilder;
+use namada_sdk::io::NullIo;
+use namada_sdk::key::common;
+use namada_sdk::masp::fs::FsShieldedUtils;
+use namada_sdk::tendermint_rpc::HttpClient;
+use namada_sdk::wallet::fs::FsWalletUtils;
+use namada_sdk::{rpc, Namada, NamadaImpl};
+use namada_token::Dec;
+use namada_utils::fees::DEFAULT_GAS_LIMIT;
+use namada_utils::gov::{
+    query_proposal, query_proposal_tally, query_proposals, query_voting_power, quorum_threshold,
+    turnout, ProposalDraft, VoteSplit,
+};
+use namada_utils::multisig::{
+    check_threshold, query_account_keys, query_signing_account, submit_or_dump, MultisigArgs,
+};
+use namada_utils::{
+    build_report_ctx, display_address, get_addresses, get_addresses_or_tagged,
+    read_wallet_password, resolve_address, CacheArgs, ConfigParams, RpcArgs,
+};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    rpc: RpcArgs,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// List the governance proposals
+    Proposals {
+        /// Only show proposals that are pending or open for voting
+        #[arg(long)]
+        active: bool,
+
+        #[command(flatten)]
+        cache: CacheArgs,
+    },
+    /// Show a proposal with its vote tally
+    Proposal {
+        id: u64,
+
+        #[command(flatten)]
+        cache: CacheArgs,
+    },
+    /// Vote on a proposal from the configured addresses
+    Vote(VoteArgs),
+    /// Write a proposal JSON skeleton with the earliest valid epochs
+    Draft {
+        #[arg(value_parser = ["default", "pgf-steward", "pgf-funding"])]
+        kind: String,
+
+        /// Author of the proposal (alias or address). Defaults to the first
+        /// configured address.
+        #[arg(long)]
+        author: Option<String>,
+
+        /// Write the skeleton to this file instead of printing it
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        #[command(flatten)]
+        cache: CacheArgs,
+    },
+    /// Check a proposal JSON against the governance parameters of the chain
+    Validate {
+        file: PathBuf,
+
+        #[command(flatten)]
+        cache: CacheArgs,
+    },
+}
+
+#[derive(ClapArgs, Debug)]
+struct VoteArgs {
+    id: u64,
+
+    #[arg(value_parser = ["yay", "nay", "abstain"])]
+    vote: String,
+
+    /// Vote only from these addresses (aliases or addresses) instead of all
+    /// the configured ones
+    #[arg(long, value_delimiter = ',')]
+    addresses: Vec<String>,
+
+    /// Vote from the address book entries with this tag
+    #[arg(long, conflicts_with = "addresses")]
+    tag: Option<String>,
+
+    /// Additional wallet keys signing for established (multisig) voters. The
+    /// key of an implicit voter is found in the wallet.
+    #[arg(short, long, value_delimiter = ',')]
+    signers: Vec<String>,
+
+    /// Wallet alias of the key paying the fees. Defaults to the first signing
+    /// key of each voter.
+    #[arg(long = "fee-payer")]
+    fee_payer: Option<String>,
+
+    #[arg(long = "gas-limit", default_value_t = DEFAULT_GAS_LIMIT)]
+    gas_limit: u64,
+
+    /// Token to pay the fees in (alias or address)
+    #[arg(long = "fee-token", default_value = "nam")]
+    fee_token: String,
+
+    // Voters without a key in the wallet are dumped with their keys known on
+    // chain. `--tx-file` needs exactly one address in `--addresses`.
+    #[command(flatten)]
+    multisig: MultisigArgs,
+}
+
+fn print_split(label: &str, split: &VoteSplit) {
+    println!(
+        "{}: {} yay, {} nay, {} abstain NAM",
+        label,
+        split.yay.to_string_native(),
+        split.nay.to_string_native(),
+        split.abstain.to_string_native()
+    );
+}
+
+fn percent(dec: Dec) -> Dec {
+    dec.checked_mul(Dec::from(100u64)).unwrap()
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    // Votes are built from the chain state, so `gov vote` doesn't take `--cache`
+    let cache = match &args.command {
+        Command::Proposals { cache, .. }
+        | Command::Proposal { cache, .. }
+        | Command::Draft { cache, .. }
+        | Command::Validate { cache, .. } => cache.clone(),
+        Command::Vote(_) => CacheArgs::default(),
+    };
+    let (sdk, config) = build_report_ctx(&args.rpc, &cache).await;
+
+    let current_epoch = rpc::query_epoch(&sdk.client)
+        .await
+        .expect("Query epoch error");
+    println!("Current epoch: {}\n", current_epoch);
+
+    match args.command {
+        Command::Proposals { active, .. } => {
+            for proposal in query_proposals(&sdk).await {
+                if active && proposal.voting_end_epoch <= current_epoch {
+                    continue;
+                }
+                println!(
+                    "#{} {}: {}",
+                    proposal.id,
+                    proposal
+                        .content
+                        .get("title")
+                        .map(String::as_str)
+                        .unwrap_or("(no title)"),
+                    proposal.get_status(current_epoch)
+                );
+                println!("  Type: {}", proposal.r#type);
+                println!("  Author: {}", proposal.author);
+                println!(
+                    "  Voting: epoch {} to {}, activation at epoch {}",
+                    proposal.voting_start_epoch,
+                    proposal.voting_end_epoch,
+                    proposal.activation_epoch
+                );
+            }
+        }
+        Command::Proposal { id, .. } => {
+            let proposal = query_proposal(&sdk, id)
+                .await
+                .expect("Proposal does not exist");
+            println!("Proposal #{}: {}", id, proposal.get_status(current_epoch));
+            println!("Type: {}", proposal.r#type);
+            println!("Author: {}", proposal.author);
+            println!(
+                "Voting: epoch {} to {}, activation at epoch {}",
+                proposal.voting_start_epoch, proposal.voting_end_epoch, proposal.activation_epoch
+            );
+            for (key, value) in &proposal.content {
+                println!("{}: {}", key, value);
+            }
+
+            let tally = query_proposal_tally(&sdk, &proposal).await;
+            println!("\n--- Tally --------");
+            print_split("Validators", &tally.validators);
+            print_split("Delegators", &tally.delegators);
+            match &tally.result {
+                Some(result) => {
+                    println!(
+                        "Total (after delegator overrides): {} yay, {} nay, {} abstain of {} NAM",
+                        result.total_yay_power.to_string_native(),
+                        result.total_nay_power.to_string_native(),
+                        result.total_abstain_power.to_string_native(),
+                        result.total_voting_power.to_string_native()
+                    );
+                    let threshold = quorum_threshold(&result.tally_type);
+                    println!(
+                        "Turnout: {}% (quorum {}%)",
+                        percent(turnout(result)),
+                        percent(threshold)
+                    );
+                    if let TallyType::LessOneHalfOverOneThirdNay = result.tally_type {
+                        println!("Steward PGF funding: passes unless the quorum is met with more nay than yay");
+                    }
+                    println!("Result: {}", result.result);
+                }
+                None => println!("Voting has not started"),
+            }
+
+            println!("\n--- Our addresses --------");
+            let wallet = sdk.wallet().await;
+            let my_addresses = get_addresses(&wallet, &config);
+            for addr in &my_addresses {
+                let label = display_address(&wallet, &config, addr);
+                let votes = tally
+                    .votes
+                    .iter()
+                    .filter(|vote| &vote.delegator == addr)
+                    .collect::<Vec<_>>();
+                if votes.is_empty() {
+                    println!("{}: has not voted", label);
+                }
+                for vote in votes {
+                    if vote.is_validator() {
+                        println!("{}: voted {} as a validator", label, vote.data);
+                    } else {
+                        println!(
+                            "{}: voted {} on the bond to {}",
+                            label, vote.data, vote.validator
+                        );
+                    }
+                }
+            }
+        }
+        Command::Vote(args) => vote(&sdk, &config, current_epoch, args).await,
+        Command::Draft {
+            kind,
+            author,
+            output,
+            ..
+        } => {
+            let author = {
+                let wallet = sdk.wallet().await;
+                match author {
+                    Some(author) => resolve_address(&wallet, &config.address_book, &author),
+                    None => get_addresses(&wallet, &config)
+                        .into_iter()
+                        .next()
+                        .expect("No configured address, pass --author"),
+                }
+            };
+            let params = rpc::query_governance_parameters(&sdk.client).await;
+            let json = ProposalDraft::skeleton(&kind, &author, current_epoch, &params).to_json();
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, json).expect("Could not write proposal file");
+                    println!("Proposal skeleton written to {}", path.display());
+                }
+                None => println!("{}", json),
+            }
+        }
+        Command::Validate { file, .. } => {
+            let json = std::fs::read_to_string(&file).expect("Could not read proposal file");
+            let draft = ProposalDraft::parse(&json).expect("Could not parse proposal file");
+            let params = rpc::query_governance_parameters(&sdk.client).await;
+            let native_token = sdk.native_token();
+            let author = &draft.proposal().author;
+            let balance = rpc::get_token_balance(&sdk.client, &native_token, author, None)
+                .await
+                .expect("Query author balance error");
+
+            let violations = draft.violations(&params, current_epoch, balance);
+            if violations.is_empty() {
+                println!("{} is valid", file.display());
+            } else {
+                println!("{} has {} violation(s):", file.display(), violations.len());
+                for violation in violations {
+                    println!("  {}", violation);
+                }
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+/// The wallet keys that can sign for the voter: its own key if it is an
+/// implicit address, plus the `--signers` keys
+async fn voter_keys(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    voter: &Address,
+    signers: &[String],
+) -> Vec<common::PublicKey> {
+    let wallet = sdk.wallet().await;
+    let mut keys = Vec::new();
+    if let Address::Implicit(pkh) = voter {
+        if let Ok(key) = wallet.find_public_key_from_implicit_addr(pkh) {
+            keys.push(key);
+        }
+    }
+    for alias in signers {
+        let key = wallet
+            .find_public_key(alias)
+            .expect("Signing key not found in wallet, run import_keys first");
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+    keys
+}
+
+async fn vote(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    config: &ConfigParams,
+    current_epoch: Epoch,
+    args: VoteArgs,
+) {
+    let password = read_wallet_password(false);
+    let fee_token = resolve_address(&*sdk.wallet().await, &config.address_book, &args.fee_token);
+    let fee_payer = match &args.fee_payer {
+        Some(alias) => Some(
+            sdk.wallet()
+                .await
+                .find_public_key(alias)
+                .expect("Fee payer key not found in wallet"),
+        ),
+        None => None,
+    };
+
+    let voters: Vec<Address> = {
+        let wallet = sdk.wallet().await;
+        if args.addresses.is_empty() {
+            get_addresses_or_tagged(&wallet, config, args.tag.as_deref())
+        } else {
+            args.addresses
+                .iter()
+                .map(|alias| resolve_address(&wallet, &config.address_book, alias))
+                .collect()
+        }
+    };
+    let voters: Vec<(String, Address)> = {
+        let wallet = sdk.wallet().await;
+        voters
+            .into_iter()
+            .map(|voter| (display_address(&wallet, config, &voter), voter))
+            .collect()
+    };
+
+    // A dumped vote already has its voter and fee payer fixed
+    if args.multisig.tx_file.is_some() {
+        assert_eq!(
+            voters.len(),
+            1,
+            "--tx-file needs exactly one voter in --addresses"
+        );
+        let (label, voter) = &voters[0];
+        let keys = voter_keys(sdk, voter, &args.signers).await;
+        let account = query_signing_account(sdk, voter).await;
+        let threshold_met =
+            check_threshold(label, &account, &keys, &args.multisig.signature_files());
+        let (tx, signing_data) = args
+            .multisig
+            .dumped_tx(&account, &keys)
+            .expect("--tx-file is set");
+        let tx_args = sdk
+            .tx_builder()
+            .signing_keys(keys)
+            .fee_token(fee_token)
+            .password(password);
+        submit_or_dump(sdk, tx, &tx_args, signing_data, threshold_met, &args.multisig, "").await;
+        return;
+    }
+
+    let proposal = query_proposal(sdk, args.id)
+        .await
+        .expect("Proposal does not exist");
+    let votes = rpc::query_proposal_votes(&sdk.client, args.id)
+        .await
+        .expect("Query proposal votes error");
+
+    for (label, voter) in &voters {
+        println!("\n{}:", label);
+        let previous = votes
+            .iter()
+            .filter(|vote| &vote.delegator == voter)
+            .map(|vote| vote.data.to_string())
+            .collect::<Vec<_>>();
+        if !previous.is_empty() {
+            println!("  Already voted {}, skipping", previous.join(", "));
+            continue;
+        }
+
+        let (is_validator, power) = query_voting_power(sdk, voter, current_epoch).await;
+        if power.is_zero() {
+            println!("  No voting power, skipping");
+            continue;
+        }
+        if !proposal.can_be_voted(current_epoch, is_validator) {
+            println!("  Voting period is closed for this address, skipping");
+            continue;
+        }
+
+        let mut keys = voter_keys(sdk, voter, &args.signers).await;
+        let dumping = args.multisig.dump_tx.is_some();
+        if dumping && keys.is_empty() {
+            // Nothing is signed here, the keys only go into the wrapper header
+            keys = query_account_keys(sdk, voter).await;
+            if keys.is_empty() {
+                keys.extend(fee_payer.clone());
+            }
+        }
+        if keys.is_empty() {
+            if dumping {
+                println!("  No key of the voter is known, pass --signers or --fee-payer");
+            } else {
+                println!("  No signing key in the wallet, pass --signers or use --dump-tx");
+            }
+            continue;
+        }
+        // A dumped vote only needs its signatures on the `--tx-file` rerun
+        let threshold_met = dumping || {
+            let account = query_signing_account(sdk, voter).await;
+            check_threshold("  Voter", &account, &keys, &[])
+        };
+
+        let mut builder = sdk
+            .new_proposal_vote(args.id, args.vote.clone(), voter.clone())
+            .signing_keys(keys)
+            .gas_limit(args.gas_limit.into())
+            .fee_token(fee_token.clone())
+            .password(password.clone());
+        if let Some(fee_payer) = &fee_payer {
+            builder = builder.wrapper_fee_payer(fee_payer.clone());
+        }
+        let (tx, signing_data) = match builder.build(sdk).await {
+            Ok(built) => built,
+            Err(e) => {
+                println!("  Could not build the vote: {}", e);
+                continue;
+            }
+        };
+
+        if !dumping && threshold_met {
+            println!(
+                "  Voting {} with {} NAM",
+                args.vote,
+                power.to_string_native()
+            );
+        }
+        let rerun_args = format!("--addresses {} ", voter);
+        submit_or_dump(
+            sdk,
+            tx,
+            &builder.tx,
+            signing_data,
+            threshold_met,
+            &args.multisig,
+            &rerun_args,
+        )
+        .await;
+    }
+}
@@ -0,0 +1,228 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use clap::{Parser, Subcommand};
+use namada_core::token;
+use namada_token::Dec;
+use namada_utils::address_book::AddressBook;
+use namada_utils::store::{Snapshot, Store};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// SQLite database written by `snapshot`
+    #[arg(long, default_value = "snapshots.db", global = true)]
+    db: PathBuf,
+
+    /// First epoch to show
+    #[arg(long = "from-epoch", global = true)]
+    from_epoch: Option<u64>,
+
+    /// Last epoch to show
+    #[arg(long = "to-epoch", global = true)]
+    to_epoch: Option<u64>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Staked ratio, supply, PGF/governance balances and staking rewards rate
+    Chain,
+    /// Total supply and MASP balance of an IBC token
+    Token { symbol: String },
+    /// Stake and rank of a validator, by name, address book label or address
+    Validator { validator: String },
+    /// Stake of the genesis allocation categories
+    Categories,
+}
+
+fn percent(dec: Dec) -> Dec {
+    dec.checked_mul(Dec::from_str("100").unwrap()).unwrap()
+}
+
+/// `+x` or `-x` from `first` to `last`
+fn change(
+    first: token::Amount,
+    last: token::Amount,
+    format: impl Fn(token::Amount) -> String,
+) -> String {
+    match last.checked_sub(first) {
+        Some(up) => format!("+{}", format(up)),
+        None => format!("-{}", format(first.checked_sub(last).unwrap())),
+    }
+}
+
+fn native(amount: token::Amount) -> String {
+    amount.to_string_native()
+}
+
+fn main() {
+    let args = Args::parse();
+    let store = Store::open(&args.db);
+    let snapshots = store.snapshots(args.from_epoch, args.to_epoch);
+    let (Some(first), Some(last)) = (snapshots.first(), snapshots.last()) else {
+        println!("No snapshots in {}", args.db.display());
+        return;
+    };
+    let span = format!(
+        "from epoch {} to {} ({} snapshots)",
+        first.epoch,
+        last.epoch,
+        snapshots.len()
+    );
+
+    match args.command {
+        Command::Chain => {
+            for snapshot in &snapshots {
+                println!(
+                    "Epoch {} (height {}, {}): staked {}%, supply {} NAM, PGF {} NAM, gov {} NAM, staking rewards {}%",
+                    snapshot.epoch,
+                    snapshot.height,
+                    snapshot.time,
+                    percent(snapshot.staked_ratio()),
+                    native(snapshot.native_supply),
+                    native(snapshot.pgf_balance),
+                    native(snapshot.gov_balance),
+                    percent(snapshot.staking_rewards_rate)
+                );
+            }
+            println!("\n--- Change {} --------", span);
+            println!(
+                "Staked ratio: {} percentage points",
+                percent(
+                    last.staked_ratio()
+                        .checked_sub(first.staked_ratio())
+                        .unwrap()
+                )
+            );
+            println!(
+                "Total stake: {} NAM",
+                change(first.total_staked, last.total_staked, native)
+            );
+            println!(
+                "Supply: {} NAM",
+                change(first.native_supply, last.native_supply, native)
+            );
+            println!(
+                "PGF balance: {} NAM",
+                change(first.pgf_balance, last.pgf_balance, native)
+            );
+            println!(
+                "Gov balance: {} NAM",
+                change(first.gov_balance, last.gov_balance, native)
+            );
+        }
+        Command::Token { symbol } => {
+            let points: Vec<_> = snapshots
+                .iter()
+                .filter_map(|snapshot| {
+                    snapshot
+                        .tokens
+                        .iter()
+                        .find(|token| token.symbol.eq_ignore_ascii_case(&symbol))
+                        .map(|token| (snapshot, token))
+                })
+                .collect();
+            let (Some((_, first)), Some((_, last))) = (points.first(), points.last()) else {
+                println!("No snapshots of {}", symbol);
+                return;
+            };
+            for (snapshot, token) in &points {
+                println!(
+                    "Epoch {} (height {}): supply {}, MASP {}",
+                    snapshot.epoch,
+                    snapshot.height,
+                    token.format_amount(token.total_supply),
+                    token.format_amount(token.masp_balance)
+                );
+            }
+            println!("\n--- Change {} --------", span);
+            let format = |amount| first.format_amount(amount);
+            println!(
+                "Supply: {} {}",
+                change(first.total_supply, last.total_supply, format),
+                first.symbol
+            );
+            println!(
+                "MASP: {} {}",
+                change(first.masp_balance, last.masp_balance, format),
+                first.symbol
+            );
+        }
+        Command::Validator { validator } => {
+            let book = AddressBook::load().expect("Could not parse the address book");
+            let validator = match book.find(&validator) {
+                Some(address) => address.to_string(),
+                None => validator,
+            };
+            let points: Vec<_> = snapshots
+                .iter()
+                .map(|snapshot| {
+                    let found = snapshot.validators.iter().find(|val| {
+                        val.address == validator
+                            || val
+                                .name
+                                .as_ref()
+                                .is_some_and(|name| name.eq_ignore_ascii_case(&validator))
+                    });
+                    (snapshot, found)
+                })
+                .collect();
+            for (snapshot, val) in &points {
+                match val {
+                    Some(val) => println!(
+                        "Epoch {} (height {}): #{}, {} NAM ({}% of stake)",
+                        snapshot.epoch,
+                        snapshot.height,
+                        val.rank,
+                        native(val.stake),
+                        percent(share(val.stake, snapshot))
+                    ),
+                    None => println!(
+                        "Epoch {} (height {}): not in the recorded top {}",
+                        snapshot.epoch,
+                        snapshot.height,
+                        snapshot.validators.len()
+                    ),
+                }
+            }
+            let found: Vec<_> = points.iter().filter_map(|(_, val)| *val).collect();
+            if let (Some(first), Some(last)) = (found.first(), found.last()) {
+                println!("\n--- Change {} --------", span);
+                println!("Stake: {} NAM", change(first.stake, last.stake, native));
+                println!("Rank: #{} -> #{}", first.rank, last.rank);
+            }
+        }
+        Command::Categories => {
+            for snapshot in &snapshots {
+                if snapshot.categories.is_empty() {
+                    continue;
+                }
+                println!("\nEpoch {} (height {}):", snapshot.epoch, snapshot.height);
+                for category in &snapshot.categories {
+                    let staked_frac = Dec::try_from(category.staked)
+                        .unwrap()
+                        .checked_div(Dec::try_from(category.genesis_balance).unwrap())
+                        .unwrap_or_default();
+                    println!(
+                        "  {}: {} NAM staked, {}% of its genesis balance, {}% of total stake",
+                        category.category,
+                        native(category.staked),
+                        percent(staked_frac),
+                        percent(share(category.staked, snapshot))
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Share of the total stake
+fn share(stake: token::Amount, snapshot: &Snapshot) -> Dec {
+    Dec::try_from(stake)
+        .unwrap()
+        .checked_div(Dec::try_from(snapshot.total_staked).unwrap())
+        .unwrap_or_default()
+}
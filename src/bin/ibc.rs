@@ -0,0 +1,131 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use clap::{Parser, Subcommand};
+use namada_core::tendermint::Time;
+use namada_sdk::io::NullIo;
+use namada_sdk::masp::fs::FsShieldedUtils;
+use namada_sdk::tendermint_rpc::HttpClient;
+use namada_sdk::wallet::fs::FsWalletUtils;
+use namada_sdk::NamadaImpl;
+use namada_utils::ibc::{
+    format_duration, query_channels, query_client, query_connections, query_pending_commitments,
+};
+use namada_utils::{build_report_ctx, CacheArgs, RpcArgs};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    rpc: RpcArgs,
+
+    #[command(flatten)]
+    cache: CacheArgs,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Show the IBC connections, channels and light clients of Namada
+    Channels {
+        /// Warn about clients expiring within this many days
+        #[arg(long = "warn-days", default_value_t = 3)]
+        warn_days: u64,
+    },
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    let (sdk, _config) = build_report_ctx(&args.rpc, &args.cache).await;
+
+    match args.command {
+        Command::Channels { warn_days } => print_channels(&sdk, warn_days).await,
+    }
+}
+
+async fn print_channels(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    warn_days: u64,
+) {
+    let now = Time::now();
+    let warn_period = Duration::from_secs(warn_days * 86400);
+
+    let connections = query_connections(sdk).await;
+    let mut clients = BTreeMap::new();
+    for (_, conn) in &connections {
+        if !clients.contains_key(conn.client_id()) {
+            let client = query_client(sdk, conn.client_id()).await;
+            clients.insert(conn.client_id().clone(), client);
+        }
+    }
+
+    println!("\n--- Clients --------");
+    for client in clients.values() {
+        println!("{} ({}):", client.client_id, client.chain_id);
+        println!("  Latest height: {}", client.latest_height);
+        println!("  Latest header time: {}", client.latest_timestamp);
+        println!(
+            "  Trusting period: {}",
+            format_duration(client.trusting_period)
+        );
+        if client.frozen {
+            println!("  WARNING: client is frozen");
+            continue;
+        }
+        match client.time_left(now) {
+            Some(left) => {
+                println!(
+                    "  Expires at: {} (in {})",
+                    client.expires_at(),
+                    format_duration(left)
+                );
+                if left < warn_period {
+                    println!("  WARNING: client expires in less than {} days", warn_days);
+                }
+            }
+            None => println!("  WARNING: client expired at {}", client.expires_at()),
+        }
+    }
+
+    println!("\n--- Connections --------");
+    for (conn_id, conn) in &connections {
+        println!(
+            "{}: {}, client {} ({}), counterparty {}",
+            conn_id,
+            conn.state(),
+            conn.client_id(),
+            clients[conn.client_id()].chain_id,
+            conn.counterparty()
+                .connection_id()
+                .map(|id| id.to_string())
+                .unwrap_or_else(|| String::from("none")),
+        );
+    }
+
+    println!("\n--- Channels --------");
+    for (port_id, channel_id, channel) in query_channels(sdk).await {
+        let conn_id = &channel.connection_hops()[0];
+        let chain_id = connections
+            .iter()
+            .find(|(id, _)| id == conn_id)
+            .map(|(_, conn)| clients[conn.client_id()].chain_id.to_string())
+            .unwrap_or_else(|| String::from("unknown chain"));
+        let pending = query_pending_commitments(sdk, &port_id, &channel_id).await;
+        println!("{}/{}:", port_id, channel_id);
+        println!("  State: {}", channel.state());
+        println!("  Connection: {} ({})", conn_id, chain_id);
+        println!(
+            "  Counterparty: {}/{}",
+            channel.counterparty().port_id(),
+            channel
+                .counterparty()
+                .channel_id()
+                .map(|id| id.to_string())
+                .unwrap_or_else(|| String::from("none"))
+        );
+        println!("  Pending packets: {}", pending);
+    }
+}
@@ -0,0 +1,54 @@
+use clap::Parser;
+use namada_utils::{
+    build_ctx, load_keys, load_mnemonic_key, read_wallet_password, RpcArgs,
+    DEFAULT_DERIVATION_PATH,
+};
+
+/// Import signing keys into the wallet, encrypted with a password
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    rpc: RpcArgs,
+
+    /// Derive the key from a BIP39 mnemonic instead of reading raw keys
+    #[arg(short, long)]
+    mnemonic: bool,
+
+    /// Derivation path used with `--mnemonic`
+    #[arg(short, long = "derivation-path", default_value = DEFAULT_DERIVATION_PATH)]
+    derivation_path: String,
+
+    /// Alias for the key derived with `--mnemonic`
+    #[arg(short, long, default_value = "key-0")]
+    alias: String,
+
+    /// Prompt for a BIP39 passphrase along with the mnemonic
+    #[arg(long = "bip39-passphrase")]
+    bip39_passphrase: bool,
+
+    /// CSV file of `address,pk` rows, relative to NAMADA_UTILS_DIR
+    #[arg(short, long = "keys-file", default_value = "config/keys.csv")]
+    keys_file: String,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    let (sdk, _config) = build_ctx(&args.rpc).await;
+
+    let password = read_wallet_password(true);
+
+    if args.mnemonic {
+        load_mnemonic_key(
+            &sdk,
+            &args.alias,
+            &args.derivation_path,
+            args.bip39_passphrase,
+            password,
+        )
+        .await;
+    } else {
+        load_keys(&sdk, &args.keys_file, password).await;
+    }
+}
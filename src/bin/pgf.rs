@@ -0,0 +1,178 @@
+use clap::Parser;
+use namada_core::token;
+use namada_governance::storage::proposal::{AddRemove, PGFAction, PGFTarget};
+use namada_sdk::{rpc, Namada};
+use namada_utils::address_book::AddressBook;
+use namada_utils::pgf::{query_pgf_payments, query_pgf_state};
+use namada_utils::{build_report_ctx, CacheArgs, RpcArgs};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    rpc: RpcArgs,
+
+    #[command(flatten)]
+    cache: CacheArgs,
+
+    /// Number of epochs to project the PGF balance over
+    #[arg(long, default_value_t = 12)]
+    epochs: u64,
+}
+
+fn format_target(book: &AddressBook, target: &PGFTarget) -> String {
+    match target {
+        PGFTarget::Internal(t) => format!(
+            "{}: {} NAM",
+            book.display(&t.target),
+            t.amount.to_string_native()
+        ),
+        PGFTarget::Ibc(t) => format!(
+            "{} over {}/{}: {} NAM",
+            t.target,
+            t.port_id,
+            t.channel_id,
+            t.amount.to_string_native()
+        ),
+    }
+}
+
+fn format_passed(passed: Option<bool>) -> &'static str {
+    match passed {
+        Some(true) => "passed",
+        Some(false) => "rejected",
+        None => "if it passes",
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    let (sdk, config) = build_report_ctx(&args.rpc, &args.cache).await;
+    let book = &config.address_book;
+
+    let native_token = sdk.wallet().await.find_address("nam").unwrap().into_owned();
+    let pgf_address = sdk.wallet().await.find_address("pgf").unwrap().into_owned();
+
+    let current_epoch = rpc::query_epoch(&sdk.client)
+        .await
+        .expect("Query epoch error");
+    println!("Current epoch: {}", current_epoch);
+
+    let state = query_pgf_state(&sdk).await;
+    let payments = query_pgf_payments(&sdk).await;
+
+    println!("\n--- Parameters --------");
+    println!(
+        "PGF inflation rate: {} ({} NAM per epoch)",
+        state.parameters.pgf_inflation_rate,
+        state.pgf_inflation(state.native_supply).to_string_native()
+    );
+    println!(
+        "Stewards inflation rate: {} ({} NAM per epoch)",
+        state.parameters.stewards_inflation_rate,
+        state
+            .stewards_inflation(state.native_supply)
+            .to_string_native()
+    );
+    println!(
+        "Maximum number of stewards: {}",
+        state.parameters.maximum_number_of_stewards
+    );
+
+    println!("\n--- Stewards --------");
+    for steward in &state.stewards {
+        println!("{}:", book.display(&steward.address));
+        for (address, share) in &steward.reward_distribution {
+            println!("  reward share {}: {}", share, book.display(address));
+        }
+    }
+
+    println!("\n--- Continuous funding --------");
+    let mut total_per_epoch = token::Amount::zero();
+    for funding in &state.fundings {
+        total_per_epoch = total_per_epoch
+            .checked_add(funding.detail.amount())
+            .unwrap();
+        println!(
+            "Proposal #{}: {} per epoch",
+            funding.id,
+            format_target(book, &funding.detail)
+        );
+        // Continuous fundings run until a proposal removes them
+        let removal = payments.iter().find(|payment| match &payment.action {
+            PGFAction::Continuous(AddRemove::Remove(target)) => {
+                target == &funding.detail && payment.is_upcoming(current_epoch)
+            }
+            _ => false,
+        });
+        match removal {
+            Some(payment) => println!(
+                "  ends at epoch {} (proposal #{}, {})",
+                payment.proposal.activation_epoch,
+                payment.proposal.id,
+                format_passed(payment.passed)
+            ),
+            None => println!("  no end epoch, paid until removed by a proposal"),
+        }
+    }
+    println!(
+        "Total: {} NAM per epoch",
+        total_per_epoch.to_string_native()
+    );
+    for payment in &payments {
+        if let PGFAction::Continuous(AddRemove::Add(target)) = &payment.action {
+            if payment.is_upcoming(current_epoch) {
+                println!(
+                    "Upcoming from epoch {} (proposal #{}, {}): {} per epoch",
+                    payment.proposal.activation_epoch,
+                    payment.proposal.id,
+                    format_passed(payment.passed),
+                    format_target(book, target)
+                );
+            }
+        }
+    }
+
+    println!("\n--- Retro funding --------");
+    let mut total_retro = token::Amount::zero();
+    for payment in &payments {
+        let PGFAction::Retro(target) = &payment.action else {
+            continue;
+        };
+        if payment.proposal.activation_epoch <= current_epoch {
+            if payment.passed == Some(true) {
+                total_retro = total_retro.checked_add(target.amount()).unwrap();
+                println!(
+                    "Paid at epoch {} (proposal #{}): {}",
+                    payment.proposal.activation_epoch,
+                    payment.proposal.id,
+                    format_target(book, target)
+                );
+            }
+        } else if payment.is_upcoming(current_epoch) {
+            println!(
+                "Upcoming at epoch {} (proposal #{}, {}): {}",
+                payment.proposal.activation_epoch,
+                payment.proposal.id,
+                format_passed(payment.passed),
+                format_target(book, target)
+            );
+        }
+    }
+    println!("Total paid: {} NAM", total_retro.to_string_native());
+
+    println!("\n--- Balance projection --------");
+    let balance = rpc::get_token_balance(&sdk.client, &native_token, &pgf_address, None)
+        .await
+        .unwrap();
+    println!(
+        "Epoch {}: {} NAM (current)",
+        current_epoch,
+        balance.to_string_native()
+    );
+    for (epoch, balance) in state.project_balance(balance, current_epoch, args.epochs) {
+        println!("Epoch {}: {} NAM", epoch, balance.to_string_native());
+    }
+    println!("Upcoming payments by proposals are not included in the projection.");
+}
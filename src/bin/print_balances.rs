@@ -1,31 +1,81 @@
+use clap::Parser;
 use namada_core::token;
 use namada_proof_of_stake::types::BondId;
 use namada_sdk::{rpc, Namada};
-use namada_utils::{build_ctx, get_addresses};
+use namada_utils::registry::get_token_registry;
+use namada_utils::shielded::{get_shielded_balances, get_viewing_keys, shielded_sync};
+use namada_utils::{build_report_ctx, display_address, get_addresses_or_tagged, CacheArgs, RpcArgs};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    rpc: RpcArgs,
+
+    #[command(flatten)]
+    cache: CacheArgs,
+
+    /// Also sync and report the shielded balances of the configured viewing keys
+    #[arg(short, long)]
+    shielded: bool,
+
+    /// Also show tokens with a zero balance
+    #[arg(short, long)]
+    all: bool,
+
+    /// Report the address book entries with this tag instead of the
+    /// configured addresses
+    #[arg(long)]
+    tag: Option<String>,
+}
 
 #[tokio::main]
 async fn main() {
-    let (sdk, config) = build_ctx().await;
-    
+    let args = Args::parse();
+    let (sdk, config) = build_report_ctx(&args.rpc, &args.cache).await;
+
     let wallet = sdk.wallet().await;
-    let native_token = wallet.find_address("nam").expect("Native token address not found in wallet");
+    let native_token = wallet
+        .find_address("nam")
+        .expect("Native token address not found in wallet")
+        .into_owned();
 
     let current_epoch = rpc::query_epoch(&sdk.client)
         .await
         .expect("Query epoch error");
     println!("Current epoch: {}\n", current_epoch);
 
-    let my_addresses = get_addresses(&wallet, &config);
+    let my_addresses = get_addresses_or_tagged(&wallet, &config, args.tag.as_deref());
+    let registry = get_token_registry(&wallet, &config);
 
+    // NAM first, followed by the other registered tokens
+    let mut tokens = vec![native_token.clone()];
+    tokens.extend(
+        registry
+            .tokens()
+            .iter()
+            .map(|token| token.address.clone())
+            .filter(|address| address != &native_token),
+    );
+
+    let mut token_totals = vec![token::Amount::zero(); tokens.len()];
     let mut total_balance = token::Amount::zero();
     let mut total_bonded = token::Amount::zero();
     let mut total_rewards = token::Amount::zero();
-    for (i, addr) in my_addresses.iter().enumerate() {
-        println!("{}:", config.transparent_addresses[i]);
-        let balance = rpc::get_token_balance(&sdk.client, &native_token, addr, None)
-            .await
-            .unwrap();
-        println!("Balance: {} NAM", balance.to_string_native());
+    for addr in &my_addresses {
+        println!("{}:", display_address(&wallet, &config, addr));
+        let mut balances = Vec::new();
+        for (j, token) in tokens.iter().enumerate() {
+            let token_balance = rpc::get_token_balance(&sdk.client, token, addr, None)
+                .await
+                .unwrap();
+            token_totals[j] = token_totals[j].checked_add(token_balance).unwrap();
+            if args.all || !token_balance.is_zero() {
+                println!("Balance: {}", registry.format_amount(token, token_balance));
+            }
+            balances.push(token_balance);
+        }
+        let balance = balances[0];
         // let rewards = rpc::rewa
         let bonds =
             rpc::enriched_bonds_and_unbonds(&sdk.client, current_epoch, &Some(addr.clone()), &None)
@@ -64,7 +114,11 @@ async fn main() {
         println!();
     }
     println!("Totals -------------------\n");
-    println!("Total balance: {} NAM", total_balance.to_string_native());
+    for (token, total) in tokens.iter().zip(&token_totals) {
+        if args.all || !total.is_zero() {
+            println!("Total balance: {}", registry.format_amount(token, *total));
+        }
+    }
     println!("Total bonded: {} NAM", total_bonded.to_string_native());
     println!(
         "Total unclaimed rewards: {} NAM",
@@ -79,4 +133,40 @@ async fn main() {
         "\nTotal transparent tokens to name: {} NAM",
         total_tokens.to_string_native()
     );
+
+    if !args.shielded {
+        return;
+    }
+
+    let viewing_keys = get_viewing_keys(&wallet, &config);
+    drop(wallet);
+
+    println!("\nShielded -------------------\n");
+    shielded_sync(&sdk, &viewing_keys).await;
+
+    let mut total_shielded = vec![token::Amount::zero(); tokens.len()];
+    for vk in &viewing_keys {
+        println!("{}:", vk.label);
+        let balances = get_shielded_balances(&sdk, &vk.key.key, &tokens).await;
+        for (i, (token, balance)) in balances.iter().enumerate() {
+            total_shielded[i] = total_shielded[i].checked_add(*balance).unwrap();
+            if !args.all && balance.is_zero() {
+                continue;
+            }
+            println!("Shielded balance: {}", registry.format_amount(token, *balance));
+        }
+        println!();
+    }
+
+    println!("Totals -------------------\n");
+    for (token, total) in tokens.iter().zip(&total_shielded) {
+        if args.all || !total.is_zero() {
+            println!("Total shielded: {}", registry.format_amount(token, *total));
+        }
+    }
+    let combined = total_tokens.checked_add(total_shielded[0]).unwrap();
+    println!(
+        "\nTotal transparent and shielded tokens to name: {} NAM",
+        combined.to_string_native()
+    );
 }
@@ -3,7 +3,7 @@ use namada_sdk::eth_bridge::ethers::core::rand;
 use namada_sdk::key::common::SecretKey;
 use namada_sdk::key::ed25519::SigScheme as ed25519SigScheme;
 use namada_sdk::key::{PublicKeyHash, RefTo, SigScheme};
-use namada_utils::build_ctx;
+use namada_utils::{build_ctx, RpcArgs};
 use rand::rngs::OsRng;
 
 #[allow(dead_code)]
@@ -19,5 +19,5 @@ fn generate_keypair() {
 
 #[tokio::main]
 async fn main() {
-    let (_sdk, _config) = build_ctx().await;
+    let (_sdk, _config) = build_ctx(&RpcArgs::default()).await;
 }
@@ -0,0 +1,77 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use clap::Parser;
+use namada_sdk::Namada;
+use namada_utils::registry::get_token_registry;
+use namada_utils::store::{take_snapshot, Snapshot, Store};
+use namada_utils::{build_report_ctx, CacheArgs, RpcArgs};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    rpc: RpcArgs,
+
+    #[command(flatten)]
+    cache: CacheArgs,
+
+    /// SQLite database the snapshots are recorded in
+    #[arg(long, default_value = "snapshots.db")]
+    db: PathBuf,
+
+    /// Number of top validators to record
+    #[arg(short, long = "num-vals", default_value_t = 25)]
+    num_vals: usize,
+
+    /// Skip the genesis allocation categories, which need the files read by
+    /// `genesis_staking`
+    #[arg(long = "skip-genesis")]
+    skip_genesis: bool,
+
+    /// Keep taking a snapshot every this many seconds
+    #[arg(long)]
+    interval: Option<u64>,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    let (sdk, config) = build_report_ctx(&args.rpc, &args.cache).await;
+    let registry = get_token_registry(&*sdk.wallet().await, &config);
+    let mut store = Store::open(&args.db);
+
+    loop {
+        let snapshot = take_snapshot(
+            &sdk,
+            &registry,
+            &config.address_book,
+            &config.chain_id,
+            args.num_vals,
+            !args.skip_genesis,
+        )
+        .await;
+
+        let Some(interval) = args.interval else {
+            insert(&mut store, &snapshot.unwrap(), &args.db);
+            break;
+        };
+        match snapshot {
+            Ok(snapshot) => insert(&mut store, &snapshot, &args.db),
+            // Keep recording through RPC errors, as `chain_info --watch` does
+            Err(e) => eprintln!("WARNING: {}, retrying in {}s", e, interval),
+        }
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+    }
+}
+
+fn insert(store: &mut Store, snapshot: &Snapshot, db: &Path) {
+    store.insert(snapshot);
+    println!(
+        "Recorded epoch {} at height {} ({}) in {}",
+        snapshot.epoch,
+        snapshot.height,
+        snapshot.time,
+        db.display()
+    );
+}
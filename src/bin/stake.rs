@@ -0,0 +1,271 @@
+use std::str::FromStr;
+
+use clap::{Args as ClapArgs, Parser, Subcommand};
+use namada_sdk::{
+    args::{self, InputAmount, TxBuilder},
+    io::NullIo,
+    key::common,
+    masp::fs::FsShieldedUtils,
+    signing::SigningTxData,
+    tendermint_rpc::HttpClient,
+    token::Amount,
+    tx::Tx,
+    wallet::fs::FsWalletUtils,
+    Namada, NamadaImpl,
+};
+use namada_utils::fees::{
+    dry_run_gas_limit, estimate_gas_limit, print_fee_estimate, DEFAULT_GAS_LIMIT,
+};
+use namada_utils::multisig::{
+    check_threshold, query_signing_account, submit_or_dump, MultisigArgs,
+};
+use namada_utils::registry::get_token_registry;
+use namada_utils::{build_ctx, read_wallet_password, resolve_address, RpcArgs};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    rpc: RpcArgs,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Bond tokens of the source to a validator
+    Bond(BondArgs),
+    /// Unbond tokens of the source from a validator
+    Unbond(BondArgs),
+    /// Withdraw the tokens of the source whose unbonding period is over
+    Withdraw(WithdrawArgs),
+}
+
+#[derive(ClapArgs, Debug)]
+struct BondArgs {
+    /// Validator (alias or address)
+    #[arg(long)]
+    validator: String,
+
+    /// Amount in whole NAM, e.g. 1.5
+    #[arg(long, required_unless_present = "tx_file")]
+    amount: Option<String>,
+
+    #[command(flatten)]
+    tx: StakeTxArgs,
+}
+
+#[derive(ClapArgs, Debug)]
+struct WithdrawArgs {
+    /// Validator (alias or address)
+    #[arg(long)]
+    validator: String,
+
+    #[command(flatten)]
+    tx: StakeTxArgs,
+}
+
+#[derive(ClapArgs, Debug)]
+struct StakeTxArgs {
+    /// Gas limit for the transaction. Estimated with a dry-run if not given.
+    #[arg(short, long = "gas-limit")]
+    gas_limit: Option<u64>,
+
+    /// Only dry-run the transaction and show the gas and fee estimate
+    #[arg(short, long)]
+    estimate: bool,
+
+    /// Safety margin added to the estimated gas, in percent
+    #[arg(long = "gas-margin", default_value_t = 20)]
+    gas_margin: u64,
+
+    /// Token to pay the fees in (alias or address)
+    #[arg(long = "fee-token", default_value = "nam")]
+    fee_token: String,
+
+    /// Wallet aliases of the signing keys, as imported by `import_keys`. The
+    /// first one pays the fees.
+    #[arg(short, long, value_delimiter = ',', default_value = "key-0")]
+    signers: Vec<String>,
+
+    /// Source account (alias or address), e.g. a multisig established account.
+    /// Defaults to the address of the first signer.
+    #[arg(long)]
+    source: Option<String>,
+
+    #[command(flatten)]
+    multisig: MultisigArgs,
+}
+
+/// The builder of one of the staking txs
+enum StakeTx {
+    Bond(args::Bond),
+    Unbond(args::Unbond),
+    Withdraw(args::Withdraw),
+}
+
+impl StakeTx {
+    fn tx_args(&self) -> &args::Tx {
+        match self {
+            StakeTx::Bond(builder) => &builder.tx,
+            StakeTx::Unbond(builder) => &builder.tx,
+            StakeTx::Withdraw(builder) => &builder.tx,
+        }
+    }
+
+    async fn build(
+        &self,
+        sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    ) -> (Tx, SigningTxData) {
+        match self {
+            StakeTx::Bond(builder) => builder.build(sdk).await.expect("unable to build bond"),
+            StakeTx::Unbond(builder) => {
+                let (tx, signing_data, _) =
+                    builder.build(sdk).await.expect("unable to build unbond");
+                (tx, signing_data)
+            }
+            StakeTx::Withdraw(builder) => {
+                builder.build(sdk).await.expect("unable to build withdraw")
+            }
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+
+    let (sdk, config) = build_ctx(&args.rpc).await;
+    let book = &config.address_book;
+
+    let (validator, amount, tx_args) = match &args.command {
+        Command::Bond(bond) | Command::Unbond(bond) => {
+            (&bond.validator, bond.amount.as_ref(), &bond.tx)
+        }
+        Command::Withdraw(withdraw) => (&withdraw.validator, None, &withdraw.tx),
+    };
+
+    // Wallet things
+    let password = read_wallet_password(false);
+
+    let mut keys: Vec<common::PublicKey> = Vec::new();
+    for alias in &tx_args.signers {
+        let key = sdk
+            .wallet()
+            .await
+            .find_public_key(alias)
+            .expect("Signing key not found in wallet, run import_keys first");
+        keys.push(key);
+    }
+    let source = resolve_address(
+        &*sdk.wallet().await,
+        book,
+        tx_args.source.as_ref().unwrap_or(&tx_args.signers[0]),
+    );
+    let validator = resolve_address(&*sdk.wallet().await, book, validator);
+
+    let native_token = sdk.native_token();
+    let fee_token = resolve_address(&*sdk.wallet().await, book, &tx_args.fee_token);
+    let registry = get_token_registry(&*sdk.wallet().await, &config);
+
+    // Check the source threshold before doing anything else
+    let account = query_signing_account(&sdk, &source).await;
+    let threshold_met = check_threshold(
+        &format!("Source {}", book.display(&source)),
+        &account,
+        &keys,
+        &tx_args.multisig.signature_files(),
+    );
+
+    let amount = match amount {
+        Some(amount) => {
+            let amount = InputAmount::from_str(amount).expect("Invalid amount");
+            namada_sdk::rpc::validate_amount(&sdk, amount, &native_token, false)
+                .await
+                .expect("Invalid amount")
+                .amount()
+        }
+        None => Amount::zero(),
+    };
+
+    let new_stake_tx = |gas_limit: u64| {
+        let with_tx_args = |tx: args::Tx| {
+            tx.signing_keys(keys.clone())
+                .gas_limit(gas_limit.into())
+                .fee_token(fee_token.clone())
+                .password(password.clone())
+        };
+        let validator = validator.clone();
+        match args.command {
+            Command::Bond(_) => StakeTx::Bond(
+                sdk.new_bond(validator, amount)
+                    .source(source.clone())
+                    .tx(with_tx_args),
+            ),
+            Command::Unbond(_) => StakeTx::Unbond(
+                sdk.new_unbond(validator, amount)
+                    .source(source.clone())
+                    .tx(with_tx_args),
+            ),
+            Command::Withdraw(_) => StakeTx::Withdraw(
+                sdk.new_withdraw(validator)
+                    .source(source.clone())
+                    .tx(with_tx_args),
+            ),
+        }
+    };
+
+    // The gas limit of a dumped tx is already fixed in its wrapper header
+    let mut gas_limit = tx_args.gas_limit.unwrap_or(DEFAULT_GAS_LIMIT);
+    if tx_args.multisig.tx_file.is_none() && (tx_args.estimate || tx_args.gas_limit.is_none()) {
+        if threshold_met {
+            // Only a bond takes tokens out of the source balance
+            let spent = match args.command {
+                Command::Bond(_) => amount,
+                _ => Amount::zero(),
+            };
+            let dry_run_limit =
+                dry_run_gas_limit(&sdk, &keys[0], &fee_token, &source, &native_token, spent).await;
+            let dry_run_builder = new_stake_tx(dry_run_limit);
+            let (dry_run_tx, signing_data) = dry_run_builder.build(&sdk).await;
+            let (gas_used, estimated_limit) = estimate_gas_limit(
+                &sdk,
+                dry_run_tx,
+                dry_run_builder.tx_args(),
+                signing_data,
+                &[],
+                tx_args.gas_margin,
+            )
+            .await
+            .expect("Dry-run of the tx failed, check the validator and amount or pass --gas-limit");
+            gas_limit = estimated_limit;
+            print_fee_estimate(&sdk, &registry, &fee_token, gas_used, gas_limit).await;
+        } else {
+            println!(
+                "Not enough signatures to dry-run the tx, using a gas limit of {}",
+                gas_limit
+            );
+        }
+        if tx_args.estimate {
+            return;
+        }
+    }
+
+    let builder = new_stake_tx(gas_limit);
+    let (tx, signing_data) = match tx_args.multisig.dumped_tx(&account, &keys) {
+        Some(dumped) => dumped,
+        None => builder.build(&sdk).await,
+    };
+
+    submit_or_dump(
+        &sdk,
+        tx,
+        builder.tx_args(),
+        signing_data,
+        threshold_met,
+        &tx_args.multisig,
+        "",
+    )
+    .await;
+}
@@ -0,0 +1,150 @@
+use std::fs::File;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use clap::{Parser, Subcommand};
+use csv::Writer;
+use namada_sdk::rpc;
+use namada_token::Dec;
+use namada_utils::supply::query_inflation_model;
+use namada_utils::{build_report_ctx, CacheArgs, RpcArgs};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    rpc: RpcArgs,
+
+    #[command(flatten)]
+    cache: CacheArgs,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Project the NAM supply, staked ratio and staking APR over the next epochs
+    Project {
+        /// Number of epochs to project
+        #[arg(long, default_value_t = 365)]
+        epochs: u64,
+
+        /// Staked ratio scenarios, e.g. 0.4,0.5,0.6. Defaults to the current
+        /// staked ratio.
+        #[arg(long = "staked-ratio", value_delimiter = ',')]
+        staked_ratios: Vec<String>,
+
+        /// CSV file to write the time series of every scenario to
+        #[arg(short, long, default_value = "supply_projection.csv")]
+        output: PathBuf,
+    },
+}
+
+fn percent(dec: Dec) -> Dec {
+    dec.checked_mul(Dec::from_str("100").unwrap()).unwrap()
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    let (sdk, _config) = build_report_ctx(&args.rpc, &args.cache).await;
+
+    match args.command {
+        Command::Project {
+            epochs,
+            staked_ratios,
+            output,
+        } => {
+            let current_epoch = rpc::query_epoch(&sdk.client)
+                .await
+                .expect("Query epoch error");
+            let model = query_inflation_model(&sdk).await;
+
+            let total_staked = rpc::get_total_staked_tokens(&sdk.client, current_epoch)
+                .await
+                .unwrap();
+            let current_ratio = Dec::try_from(total_staked)
+                .unwrap()
+                .checked_div(Dec::try_from(model.pgf.native_supply).unwrap())
+                .unwrap();
+            let staked_ratios = if staked_ratios.is_empty() {
+                vec![current_ratio]
+            } else {
+                staked_ratios
+                    .iter()
+                    .map(|ratio| Dec::from_str(ratio).expect("Invalid staked ratio"))
+                    .collect()
+            };
+
+            println!("Current epoch: {}", current_epoch);
+            println!(
+                "Effective native supply: {} NAM",
+                model.pgf.native_supply.to_string_native()
+            );
+            println!("Staked ratio: {}%", percent(current_ratio));
+            println!(
+                "Target staked ratio: {}%, max PoS inflation: {}%",
+                percent(model.pos_params.target_staked_ratio),
+                percent(model.pos_params.max_inflation_rate)
+            );
+            println!(
+                "PGF inflation: {}%, stewards inflation: {}%",
+                percent(model.pgf.parameters.pgf_inflation_rate),
+                percent(model.pgf.parameters.stewards_inflation_rate)
+            );
+
+            let file = File::create(&output).unwrap();
+            let mut wtr = Writer::from_writer(file);
+            wtr.write_record([
+                "Scenario",
+                "Epoch",
+                "Supply",
+                "Staked",
+                "Staked ratio",
+                "PoS inflation",
+                "Inflation rate",
+                "Staking APR",
+                "PGF balance",
+            ])
+            .unwrap();
+
+            for staked_ratio in staked_ratios {
+                let points = model.project(current_epoch, epochs, staked_ratio);
+                for point in &points {
+                    wtr.write_record(&[
+                        staked_ratio.to_string(),
+                        point.epoch.to_string(),
+                        point.supply.to_string_native(),
+                        point.staked.to_string_native(),
+                        point.staked_ratio.to_string(),
+                        point.pos_inflation.to_string_native(),
+                        point.inflation_rate.to_string(),
+                        point.staking_apr.to_string(),
+                        point.pgf_balance.to_string_native(),
+                    ])
+                    .unwrap();
+                }
+
+                if let Some(last) = points.last() {
+                    println!(
+                        "\n--- {}% staked, after {} epochs --------",
+                        percent(staked_ratio),
+                        epochs
+                    );
+                    println!(
+                        "Supply at epoch {}: {} NAM",
+                        last.epoch,
+                        last.supply.to_string_native()
+                    );
+                    println!("PoS inflation rate: {}%", percent(last.inflation_rate));
+                    println!("Staking APR: {}%", percent(last.staking_apr));
+                    println!("PGF balance: {} NAM", last.pgf_balance.to_string_native());
+                }
+            }
+
+            wtr.flush().unwrap();
+            println!("\nData written to {}\n", output.display());
+        }
+    }
+}
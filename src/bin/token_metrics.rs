@@ -1,37 +1,47 @@
-use namada_sdk::{collections::HashMap, rpc, Namada};
-use namada_utils::{build_ctx, get_address_from_ibc_denom};
-
-pub fn get_mainnet_ibc_nicknames() -> HashMap<String, String> {
-    HashMap::from_iter(vec![
-        (
-            String::from("transfer/channel-1/uosmo"),
-            String::from("OSMO"),
-        ),
-        (
-            String::from("transfer/channel-2/uatom"),
-            String::from("ATOM"),
-        ),
-        (String::from("transfer/channel-3/utia"), String::from("TIA")),
-        (
-            String::from("transfer/channel-0/stuosmo"),
-            String::from("stOSMO"),
-        ),
-        (
-            String::from("transfer/channel-0/stuatom"),
-            String::from("stATOM"),
-        ),
-        (
-            String::from("transfer/channel-0/stutia"),
-            String::from("stTIA"),
-        ),
-    ])
+use std::str::FromStr;
+
+use clap::Parser;
+use namada_core::token;
+use namada_sdk::{rpc, Namada};
+use namada_token::Dec;
+use namada_utils::ibc::query_rate_limit_usage;
+use namada_utils::registry::get_token_registry;
+use namada_utils::shielded::{query_masp_epochs_per_year, query_masp_reward_info};
+use namada_utils::{build_report_ctx, CacheArgs, RpcArgs};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    rpc: RpcArgs,
+
+    #[command(flatten)]
+    cache: CacheArgs,
+
+    /// Flag tokens whose IBC mint or throughput usage is at least this
+    /// percentage of the limit
+    #[arg(long = "limit-warn-percent", default_value_t = 80)]
+    limit_warn_percent: u64,
+}
+
+/// Usage as a percentage of the limit
+fn usage_percent(used: token::Amount, limit: token::Amount) -> String {
+    if limit.is_zero() {
+        return String::from("limit is zero");
+    }
+    let frac = Dec::try_from(used)
+        .unwrap()
+        .checked_div(Dec::try_from(limit).unwrap())
+        .unwrap();
+    format!("{}%", frac.checked_mul(Dec::from_str("100").unwrap()).unwrap())
 }
 
 #[tokio::main]
 async fn main() {
-    let (sdk, config) = build_ctx().await;
+    let args = Args::parse();
+    let (sdk, config) = build_report_ctx(&args.rpc, &args.cache).await;
 
-    // let native_token = sdk.wallet().await.find_address("nam").unwrap().into_owned();
+    let native_token = sdk.wallet().await.find_address("nam").unwrap().into_owned();
     let masp_address = sdk
         .wallet()
         .await
@@ -39,39 +49,160 @@ async fn main() {
         .unwrap()
         .into_owned();
 
-    let ibc_tokens = config.ibc_tokens;
-    let ibc_nicknames = get_mainnet_ibc_nicknames();
+    let registry = get_token_registry(&*sdk.wallet().await, &config);
 
     println!("\n--- Non-native tokens in Config --------");
-    for trace in &ibc_tokens {
-        if let Some(token) = ibc_nicknames.get(trace) {
-            println!("{}: {}", token, trace);
-        } else {
-            println!("{trace}");
-        }
+    for token in registry.ibc_tokens() {
+        println!(
+            "{}: {} (from {})",
+            token.symbol,
+            token.trace.as_ref().unwrap(),
+            token.origin_chain
+        );
     }
 
     println!("\n--- Total supply in Namada --------");
-    for denom in &ibc_tokens {
-        let token = ibc_nicknames.get(denom).unwrap_or(denom);
-        let total_supply =
-            rpc::get_token_total_supply(&sdk.client, &get_address_from_ibc_denom(denom))
+    for token in registry.ibc_tokens() {
+        let total_supply = rpc::get_token_total_supply(&sdk.client, &token.address)
+            .await
+            .expect("Query total supply error");
+        println!("{}: {}", token.symbol, token.format_amount(total_supply));
+    }
+
+    println!("\n--- Total supply in the MASP --------");
+    for token in registry.ibc_tokens() {
+        let masp_balance =
+            rpc::get_token_balance(&sdk.client, &token.address, &masp_address, None)
                 .await
                 .expect("Query total supply error");
-        println!("{}: {}", token, total_supply.to_string_native());
+        println!("{}: {}", token.symbol, token.format_amount(masp_balance));
     }
 
-    println!("\n--- Total supply in the MASP --------");
-    for denom in &ibc_tokens {
-        let token = ibc_nicknames.get(denom).unwrap_or(denom);
-        let masp_balance = rpc::get_token_balance(
-            &sdk.client,
-            &get_address_from_ibc_denom(denom),
-            &masp_address,
-            None,
-        )
+    println!("\n--- IBC limits --------");
+    let mut near_cap = Vec::new();
+    for token in registry.ibc_tokens() {
+        let usage = query_rate_limit_usage(&sdk, &token.address)
+            .await
+            .expect("Query IBC rate limits error");
+        println!("{}:", token.symbol);
+        println!(
+            "  Minted: {} of {} ({}), {} left",
+            token.format_amount(usage.minted),
+            token.format_amount(usage.mint_limit),
+            usage_percent(usage.minted, usage.mint_limit),
+            token.format_amount(usage.mint_headroom())
+        );
+        println!(
+            "  Throughput this epoch: {} of {} ({}), {} left",
+            token.format_amount(usage.throughput),
+            token.format_amount(usage.throughput_limit),
+            usage_percent(usage.throughput, usage.throughput_limit),
+            token.format_amount(usage.throughput_headroom())
+        );
+        if usage.is_near_cap(args.limit_warn_percent) {
+            near_cap.push(token.symbol.as_str());
+        }
+    }
+    if !near_cap.is_empty() {
+        println!(
+            "\nWARNING: at or above {}% of an IBC limit: {}",
+            args.limit_warn_percent,
+            near_cap.join(", ")
+        );
+    }
+
+    // NAM (if not in the registry) followed by the registered tokens
+    let mut tokens = Vec::new();
+    if registry.get(&native_token).is_none() {
+        tokens.push((String::from("NAM"), native_token.clone()));
+    }
+    for token in registry.tokens() {
+        tokens.push((token.symbol.clone(), token.address.clone()));
+    }
+
+    println!("\n--- Fraction of supply shielded --------");
+    for (token, address) in &tokens {
+        let total_supply = rpc::get_token_total_supply(&sdk.client, address)
+            .await
+            .expect("Query total supply error");
+        let masp_balance = rpc::get_token_balance(&sdk.client, address, &masp_address, None)
+            .await
+            .expect("Query MASP balance error");
+        if total_supply.is_zero() {
+            println!("{}: no supply", token);
+            continue;
+        }
+        let frac = Dec::try_from(masp_balance)
+            .unwrap()
+            .checked_div(Dec::try_from(total_supply).unwrap())
+            .unwrap();
+        println!(
+            "{}: {}%",
+            token,
+            frac.checked_mul(Dec::from_str("100").unwrap()).unwrap()
+        );
+    }
+
+    println!("\n--- Shielded rewards --------");
+    let masp_epochs_per_year = query_masp_epochs_per_year(&sdk).await;
+    let masp_epoch = rpc::query_masp_epoch(&sdk.client)
         .await
-        .expect("Query total supply error");
-        println!("{}: {}", token, masp_balance.to_string_native());
+        .expect("Query MASP epoch error");
+    println!(
+        "MASP epoch: {} ({} MASP epochs per year)\n",
+        masp_epoch, masp_epochs_per_year
+    );
+    for (token, address) in &tokens {
+        let Some(info) = query_masp_reward_info(&sdk, address)
+            .await
+            .expect("Query MASP reward parameters error")
+        else {
+            println!("{}: no shielded rewards\n", token);
+            continue;
+        };
+        println!("{}:", token);
+        println!(
+            "  Max reward rate: {}%",
+            info.max_reward_rate
+                .checked_mul(Dec::from_str("100").unwrap())
+                .unwrap()
+        );
+        println!(
+            "  Target locked amount: {}",
+            rpc::format_denominated_amount(&sdk.client, &sdk.io, address, info.locked_amount_target)
+                .await
+        );
+        println!(
+            "  Locked amount last epoch: {}",
+            rpc::format_denominated_amount(&sdk.client, &sdk.io, address, info.last_locked_amount)
+                .await
+        );
+        println!("  Kp gain: {}\n  Kd gain: {}", info.kp_gain, info.kd_gain);
+        println!(
+            "  Current shielded reward rate: {}%\n",
+            info.reward_rate(masp_epochs_per_year)
+                .checked_mul(Dec::from_str("100").unwrap())
+                .unwrap()
+        );
+    }
+
+    println!("--- MASP conversion state --------");
+    let conversions = rpc::query_conversions(&sdk.client)
+        .await
+        .expect("Query conversions error");
+    for (token, address) in &tokens {
+        let token_conversions = conversions
+            .values()
+            .filter(|(conv_token, ..)| conv_token == address)
+            .collect::<Vec<_>>();
+        match token_conversions.iter().map(|(_, _, _, epoch, _)| *epoch).max() {
+            Some(latest) => println!(
+                "{}: {} conversions, latest at MASP epoch {}",
+                token,
+                token_conversions.len(),
+                latest
+            ),
+            None => println!("{}: no conversions", token),
+        }
     }
 }
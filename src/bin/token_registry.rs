@@ -0,0 +1,64 @@
+use clap::{Parser, Subcommand};
+use namada_sdk::{rpc, Namada};
+use namada_utils::registry::get_token_registry;
+use namada_utils::{build_report_ctx, CacheArgs, RpcArgs};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    rpc: RpcArgs,
+
+    #[command(flatten)]
+    cache: CacheArgs,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Show the tokens configured in the registry
+    List,
+    /// Show IBC tokens that exist on chain but are missing from the registry
+    Missing,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    let (sdk, config) = build_report_ctx(&args.rpc, &args.cache).await;
+
+    let registry = get_token_registry(&*sdk.wallet().await, &config);
+
+    match args.command {
+        Command::List => {
+            for token in registry.tokens() {
+                println!("{}:", token.symbol);
+                println!("  Address: {}", token.address);
+                if let Some(trace) = &token.trace {
+                    println!("  Trace: {}", trace);
+                }
+                println!("  Decimals: {}", token.decimals);
+                println!("  Origin chain: {}", token.origin_chain);
+            }
+        }
+        Command::Missing => {
+            let ibc_tokens = rpc::query_ibc_tokens(&sdk, None, None)
+                .await
+                .expect("Query IBC tokens error");
+            let missing = ibc_tokens
+                .iter()
+                .filter(|(_, address)| registry.get(address).is_none())
+                .collect::<Vec<_>>();
+            println!(
+                "{} of {} IBC tokens on chain are not in the registry\n",
+                missing.len(),
+                ibc_tokens.len()
+            );
+            for (trace, address) in missing {
+                println!("{}: {}", trace, address);
+            }
+        }
+    }
+}
@@ -0,0 +1,324 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use clap::{Args as ClapArgs, Parser, Subcommand};
+use namada_core::ibc::core::host::types::identifiers::{ChannelId, PortId};
+use namada_sdk::{
+    args::{InputAmount, TxBuilder},
+    io::NullIo,
+    key::common,
+    masp::fs::FsShieldedUtils,
+    masp_primitives::transaction::components::sapling::builder::RngBuildParams,
+    tendermint_rpc::HttpClient,
+    tx::ProcessTxResponse,
+    wallet::fs::FsWalletUtils,
+    Namada, NamadaImpl, TransferSource,
+};
+use namada_utils::fees::{
+    dry_run_gas_limit, estimate_gas_limit, print_fee_estimate, DEFAULT_GAS_LIMIT,
+};
+use namada_utils::ibc::{sent_packets, wait_for_packet, PacketOutcome};
+use namada_utils::multisig::{check_threshold, query_signing_account, submit_or_dump, MultisigArgs};
+use namada_utils::registry::get_token_registry;
+use namada_utils::{build_ctx, read_csv_to_vec, read_wallet_password, resolve_address, RpcArgs};
+use rand_core::OsRng;
+use serde::Deserialize;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    rpc: RpcArgs,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Send tokens over an IBC channel to a receiver on the counterparty chain
+    Ibc(IbcTransferArgs),
+}
+
+#[derive(ClapArgs, Debug)]
+struct IbcTransferArgs {
+    /// Channel to send over, e.g. channel-1
+    #[arg(long)]
+    channel: String,
+
+    #[arg(long, default_value = "transfer")]
+    port: String,
+
+    /// Token symbol from the registry, wallet alias or address
+    #[arg(long)]
+    token: String,
+
+    /// Receiver address on the counterparty chain
+    #[arg(long, required_unless_present_any = ["batch", "tx_file"])]
+    receiver: Option<String>,
+
+    /// Amount in whole tokens, e.g. 1.5
+    #[arg(long, required_unless_present_any = ["batch", "tx_file"])]
+    amount: Option<String>,
+
+    /// CSV file (relative to NAMADA_UTILS_DIR) with `receiver,amount,memo`
+    /// rows, one transfer each
+    #[arg(long, conflicts_with_all = ["receiver", "amount", "tx_file"])]
+    batch: Option<String>,
+
+    /// Memo of the IBC packet, used for rows of the batch without one
+    #[arg(long)]
+    memo: Option<String>,
+
+    /// Timeout height on the counterparty chain
+    #[arg(long = "timeout-height")]
+    timeout_height: Option<u64>,
+
+    /// Timeout in seconds from now. Without any timeout, the packet times out
+    /// after an hour.
+    #[arg(long = "timeout-secs")]
+    timeout_secs: Option<u64>,
+
+    /// Gas limit for each transfer. Estimated with a dry-run if not given.
+    #[arg(long = "gas-limit")]
+    gas_limit: Option<u64>,
+
+    /// Only dry-run the transfers and show the gas and fee estimates
+    #[arg(long, conflicts_with = "tx_file")]
+    estimate: bool,
+
+    /// Safety margin added to the estimated gas, in percent
+    #[arg(long = "gas-margin", default_value_t = 20)]
+    gas_margin: u64,
+
+    /// Token to pay the fees in (alias or address)
+    #[arg(long = "fee-token", default_value = "nam")]
+    fee_token: String,
+
+    /// Wallet aliases of the signing keys. The first one pays the fees.
+    #[arg(short, long, value_delimiter = ',', default_value = "key-0")]
+    signers: Vec<String>,
+
+    /// Source account (alias or address). Defaults to the address of the
+    /// first signer.
+    #[arg(long)]
+    source: Option<String>,
+
+    #[command(flatten)]
+    multisig: MultisigArgs,
+
+    /// Don't wait for the packets to be acknowledged or to time out
+    #[arg(long = "no-wait")]
+    no_wait: bool,
+
+    /// Seconds between checks of the packet status
+    #[arg(long = "poll-secs", default_value_t = 10)]
+    poll_secs: u64,
+
+    /// Give up waiting for a packet after this many seconds
+    #[arg(long = "wait-secs", default_value_t = 1800)]
+    wait_secs: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct IbcTransferTarget {
+    receiver: String,
+    amount: String,
+    memo: Option<String>,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    match args.command {
+        Command::Ibc(ibc_args) => ibc_transfer(&args.rpc, ibc_args).await,
+    }
+}
+
+async fn ibc_transfer(rpc: &RpcArgs, args: IbcTransferArgs) {
+    let (sdk, config) = build_ctx(rpc).await;
+    let book = &config.address_book;
+
+    let password = read_wallet_password(false);
+
+    let registry = get_token_registry(&*sdk.wallet().await, &config);
+    let token = match registry.get_by_symbol(&args.token) {
+        Some(token) => token.address.clone(),
+        None => resolve_address(&*sdk.wallet().await, book, &args.token),
+    };
+    let fee_token = resolve_address(&*sdk.wallet().await, book, &args.fee_token);
+    let port_id = PortId::from_str(&args.port).expect("Invalid port id");
+    let channel_id = ChannelId::from_str(&args.channel).expect("Invalid channel id");
+
+    let mut keys: Vec<common::PublicKey> = Vec::new();
+    for alias in &args.signers {
+        let key = sdk
+            .wallet()
+            .await
+            .find_public_key(alias)
+            .expect("Signing key not found in wallet, run import_keys first");
+        keys.push(key);
+    }
+    let source = resolve_address(
+        &*sdk.wallet().await,
+        book,
+        args.source.as_ref().unwrap_or(&args.signers[0]),
+    );
+
+    let account = query_signing_account(&sdk, &source).await;
+    let threshold_met = check_threshold(
+        &format!("Source {}", book.display(&source)),
+        &account,
+        &keys,
+        &args.multisig.signature_files(),
+    );
+
+    // A dumped tx already has its transfer and gas limit fixed
+    if let Some((tx, signing_data)) = args.multisig.dumped_tx(&account, &keys) {
+        let tx_args = sdk
+            .tx_builder()
+            .signing_keys(keys.clone())
+            .fee_token(fee_token.clone())
+            .password(password.clone());
+        let response =
+            submit_or_dump(&sdk, tx, &tx_args, signing_data, threshold_met, &args.multisig, "")
+                .await;
+        if let (Some(response), false) = (response, args.no_wait) {
+            wait_for_packets(&sdk, &response, &args).await;
+        }
+        return;
+    }
+
+    let targets = match &args.batch {
+        Some(path) => read_csv_to_vec::<IbcTransferTarget>(path).expect("Failed to read CSV"),
+        None => vec![IbcTransferTarget {
+            receiver: args.receiver.clone().unwrap(),
+            amount: args.amount.clone().unwrap(),
+            memo: None,
+        }],
+    };
+
+    let new_ibc_transfer_builder = |target: &IbcTransferTarget, gas_limit: u64| {
+        let amount = InputAmount::from_str(&target.amount).expect("Invalid amount");
+        let mut builder = sdk
+            .new_ibc_transfer(
+                TransferSource::Address(source.clone()),
+                target.receiver.clone(),
+                token.clone(),
+                amount,
+                channel_id.clone(),
+                false,
+            )
+            .port_id(port_id.clone())
+            .signing_keys(keys.clone())
+            .gas_limit(gas_limit.into())
+            .fee_token(fee_token.clone())
+            .password(password.clone());
+        if let Some(height) = args.timeout_height {
+            builder = builder.timeout_height(height);
+        }
+        if let Some(secs) = args.timeout_secs {
+            builder = builder.timeout_sec_offset(secs);
+        }
+        if let Some(memo) = target.memo.as_ref().or(args.memo.as_ref()) {
+            builder = builder.ibc_memo(memo.clone());
+        }
+        builder
+    };
+
+    for target in &targets {
+        println!(
+            "\nSending {} {} to {} over {}/{}",
+            target.amount, args.token, target.receiver, port_id, channel_id
+        );
+
+        let mut gas_limit = args.gas_limit.unwrap_or(DEFAULT_GAS_LIMIT);
+        if args.estimate || args.gas_limit.is_none() {
+            if threshold_met {
+                let amount = InputAmount::from_str(&target.amount).expect("Invalid amount");
+                let amount = namada_sdk::rpc::validate_amount(&sdk, amount, &token, false)
+                    .await
+                    .expect("Invalid amount")
+                    .amount();
+                let dry_run_limit =
+                    dry_run_gas_limit(&sdk, &keys[0], &fee_token, &source, &token, amount).await;
+                let dry_run_builder = new_ibc_transfer_builder(target, dry_run_limit);
+                let (dry_run_tx, signing_data, _) = dry_run_builder
+                    .build(&sdk, &mut RngBuildParams::new(OsRng))
+                    .await
+                    .expect("unable to build IBC transfer");
+                let (gas_used, estimated_limit) = estimate_gas_limit(
+                    &sdk,
+                    dry_run_tx,
+                    &dry_run_builder.tx,
+                    signing_data,
+                    &[],
+                    args.gas_margin,
+                )
+                .await
+                .expect("Dry-run of the transfer failed, check the target or pass --gas-limit");
+                gas_limit = estimated_limit;
+                print_fee_estimate(&sdk, &registry, &fee_token, gas_used, gas_limit).await;
+            } else {
+                println!(
+                    "Not enough signatures to dry-run the transfer, using a gas limit of {}",
+                    gas_limit
+                );
+            }
+            if args.estimate {
+                continue;
+            }
+        }
+
+        let builder = new_ibc_transfer_builder(target, gas_limit);
+        let (tx, signing_data, _) = builder
+            .build(&sdk, &mut RngBuildParams::new(OsRng))
+            .await
+            .expect("unable to build IBC transfer");
+
+        let response =
+            submit_or_dump(&sdk, tx, &builder.tx, signing_data, threshold_met, &args.multisig, "")
+                .await;
+        if let (Some(response), false) = (response, args.no_wait) {
+            wait_for_packets(&sdk, &response, &args).await;
+        }
+    }
+}
+
+async fn wait_for_packets(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    response: &ProcessTxResponse,
+    args: &IbcTransferArgs,
+) {
+    let packets = sent_packets(response);
+    if packets.is_empty() {
+        println!("No IBC packet was sent");
+        return;
+    }
+    for packet in packets {
+        println!(
+            "Waiting for packet {} on {}/{}",
+            packet.sequence, packet.port_id, packet.channel_id
+        );
+        let outcome = wait_for_packet(
+            sdk,
+            &packet,
+            Duration::from_secs(args.poll_secs),
+            Duration::from_secs(args.wait_secs),
+        )
+        .await;
+        match outcome {
+            Some(PacketOutcome::Acknowledged) => println!("Packet acknowledged"),
+            Some(PacketOutcome::TimedOut) => {
+                println!("Packet timed out, the tokens were refunded")
+            }
+            Some(PacketOutcome::Unknown) => {
+                println!("Packet acknowledged or timed out, the event is no longer available")
+            }
+            None => println!(
+                "Packet still pending after {} seconds, check `ibc channels` later",
+                args.wait_secs
+            ),
+        }
+    }
+}
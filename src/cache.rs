@@ -0,0 +1,142 @@
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS responses (
+    path TEXT NOT NULL,
+    data TEXT NOT NULL,
+    height INTEGER NOT NULL,
+    result TEXT NOT NULL,
+    fetched_at INTEGER NOT NULL,
+    PRIMARY KEY (path, data, height)
+);
+";
+
+/// An `abci_query`: its path, hex-encoded data and height, 0 for the latest
+/// block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheKey {
+    pub path: String,
+    #[serde(default)]
+    pub data: String,
+    #[serde(default)]
+    pub height: u64,
+}
+
+impl CacheKey {
+    /// Key of a JSON-RPC request, if it is an `abci_query`
+    pub fn from_request(request: &Value) -> Option<Self> {
+        if request["method"] != "abci_query" {
+            return None;
+        }
+        let params = &request["params"];
+        let height = match &params["height"] {
+            Value::String(height) => height.parse().ok()?,
+            Value::Number(height) => height.as_u64()?,
+            _ => 0,
+        };
+        Some(Self {
+            path: params["path"].as_str()?.to_string(),
+            data: params["data"].as_str().unwrap_or_default().to_lowercase(),
+            height,
+        })
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+/// SQLite cache of `abci_query` results. Results pinned to a height never
+/// expire, results of the latest block expire after `ttl`.
+pub struct QueryCache {
+    conn: Mutex<Connection>,
+    ttl: Duration,
+}
+
+impl QueryCache {
+    pub fn open(path: impl AsRef<Path>, ttl: Duration) -> Self {
+        let conn = Connection::open(path).expect("Could not open the query cache");
+        conn.execute_batch(SCHEMA)
+            .expect("Could not create the query cache table");
+        Self {
+            conn: Mutex::new(conn),
+            ttl,
+        }
+    }
+
+    /// The cached `result` of a query, if still fresh
+    pub fn get(&self, key: &CacheKey) -> Option<Value> {
+        self.get_at(key, now())
+    }
+
+    fn get_at(&self, key: &CacheKey, now: i64) -> Option<Value> {
+        let (result, fetched_at): (String, i64) = self
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT result, fetched_at FROM responses WHERE path = ?1 AND data = ?2 AND height = ?3",
+                params![key.path, key.data, key.height as i64],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .expect("Could not read the query cache")?;
+        if key.height == 0 && now - fetched_at > self.ttl.as_secs() as i64 {
+            return None;
+        }
+        serde_json::from_str(&result).ok()
+    }
+
+    /// Cache the `result` of a successful query
+    pub fn insert(&self, key: &CacheKey, result: &Value) {
+        self.insert_at(key, result, now());
+    }
+
+    fn insert_at(&self, key: &CacheKey, result: &Value, now: i64) {
+        self.conn
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT OR REPLACE INTO responses (path, data, height, result, fetched_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![key.path, key.data, key.height as i64, result.to_string(), now],
+            )
+            .expect("Could not write the query cache");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_latest_queries_expire() {
+        let cache = QueryCache::open(":memory:", Duration::from_secs(60));
+        let key = |height: &str| {
+            CacheKey::from_request(&json!({
+                "method": "abci_query",
+                "params": { "path": "/shell/epoch", "data": "", "height": height },
+            }))
+            .unwrap()
+        };
+        let result = json!({ "response": { "code": 0, "value": "BQAAAAAAAAA=" } });
+        cache.insert_at(&key("0"), &result, 1_000);
+        cache.insert_at(&key("1050"), &result, 1_000);
+
+        assert_eq!(cache.get_at(&key("0"), 1_060), Some(result.clone()));
+        assert_eq!(cache.get_at(&key("0"), 1_061), None);
+        // Pinned to a height, never expires
+        assert_eq!(cache.get_at(&key("1050"), 1_000_000), Some(result));
+        assert_eq!(cache.get_at(&key("1049"), 1_000), None);
+    }
+}
@@ -0,0 +1,258 @@
+use namada_core::chain::{BlockHeight, Epoch};
+use namada_sdk::io::NullIo;
+use namada_sdk::masp::fs::FsShieldedUtils;
+use namada_sdk::state::LastBlock;
+use namada_sdk::tendermint_rpc::HttpClient;
+use namada_sdk::wallet::fs::FsWalletUtils;
+use namada_sdk::{rpc, NamadaImpl};
+
+/// Blocks between the one that meets both epoch conditions and the first
+/// block of the new epoch, to line up with Tendermint's validator set delay
+pub const EPOCH_SWITCH_BLOCKS_DELAY: u64 = 2;
+
+/// Average and spread of the time between recent blocks, in seconds
+#[derive(Debug, Clone)]
+pub struct BlockTimes {
+    pub mean: f64,
+    pub std_dev: f64,
+    pub samples: usize,
+}
+
+impl BlockTimes {
+    /// From the unix timestamps of consecutive blocks. `None` with fewer than
+    /// two blocks.
+    pub fn from_timestamps(timestamps: &[i64]) -> Option<Self> {
+        let intervals: Vec<f64> = timestamps
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]) as f64)
+            .collect();
+        let samples = intervals.len();
+        if samples == 0 {
+            return None;
+        }
+        let mean = intervals.iter().sum::<f64>() / samples as f64;
+        let variance = intervals
+            .iter()
+            .map(|interval| (interval - mean).powi(2))
+            .sum::<f64>()
+            / samples as f64;
+        Some(Self {
+            mean,
+            std_dev: variance.sqrt(),
+            samples,
+        })
+    }
+
+    /// 95% confidence interval of the average block time
+    pub fn mean_range(&self) -> (f64, f64) {
+        let margin = 1.96 * self.std_dev / (self.samples as f64).sqrt();
+        (
+            (self.mean - margin).max(self.mean / 2.0),
+            self.mean + margin,
+        )
+    }
+}
+
+/// Conditions of the current epoch: a new epoch is queued by the first block
+/// at or above `first_height + min_num_of_blocks` whose time is at or after
+/// `first_time + min_duration`
+#[derive(Debug, Clone)]
+pub struct EpochSchedule {
+    pub epoch: Epoch,
+    pub first_height: u64,
+    /// Unix timestamp of the first block of the epoch
+    pub first_time: i64,
+    pub min_num_of_blocks: u64,
+    pub min_duration: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct EpochPrediction {
+    pub epoch: Epoch,
+    /// First block height of the epoch at the average block time
+    pub height: u64,
+    /// Unix timestamp of that block
+    pub time: i64,
+    pub height_range: (u64, u64),
+    pub time_range: (i64, i64),
+}
+
+impl EpochSchedule {
+    pub fn min_start_height(&self) -> u64 {
+        self.first_height + self.min_num_of_blocks
+    }
+
+    pub fn min_start_time(&self) -> i64 {
+        self.first_time + self.min_duration
+    }
+
+    pub fn time_condition_met(&self, last_time: i64) -> bool {
+        last_time >= self.min_start_time()
+    }
+
+    pub fn block_condition_met(&self, last_height: u64) -> bool {
+        last_height >= self.min_start_height()
+    }
+
+    /// First heights and times of the next `count` epochs, assuming blocks
+    /// keep coming every `block_time` seconds after the last block
+    fn starts_at(
+        &self,
+        last_height: u64,
+        last_time: i64,
+        block_time: f64,
+        count: u64,
+    ) -> Vec<(u64, i64)> {
+        let mut schedule = self.clone();
+        let (mut ref_height, mut ref_time) = (last_height, last_time);
+        let mut starts = Vec::new();
+        for _ in 0..count {
+            // First block whose time meets the time condition. Once it is met
+            // at the reference block the block condition alone decides.
+            let time_left = schedule.min_start_time() - ref_time;
+            let queued_height = if time_left > 0 {
+                let time_height = ref_height + (time_left as f64 / block_time).ceil() as u64;
+                schedule.min_start_height().max(time_height)
+            } else {
+                schedule.min_start_height()
+            };
+            let height = (queued_height + EPOCH_SWITCH_BLOCKS_DELAY).max(ref_height + 1);
+            let time = ref_time + ((height - ref_height) as f64 * block_time).round() as i64;
+            starts.push((height, time));
+
+            schedule.first_height = height;
+            schedule.first_time = time;
+            ref_height = height;
+            ref_time = time;
+        }
+        starts
+    }
+
+    /// Predict the start of the next `count` epochs from the last block and
+    /// the observed block times. The ranges span the predictions at both ends
+    /// of the confidence interval of the average block time.
+    pub fn predict(
+        &self,
+        last_height: u64,
+        last_time: i64,
+        block_times: &BlockTimes,
+        count: u64,
+    ) -> Vec<EpochPrediction> {
+        let (fast, slow) = block_times.mean_range();
+        let expected = self.starts_at(last_height, last_time, block_times.mean, count);
+        let fast = self.starts_at(last_height, last_time, fast, count);
+        let slow = self.starts_at(last_height, last_time, slow, count);
+
+        expected
+            .into_iter()
+            .zip(fast.into_iter().zip(slow))
+            .enumerate()
+            .map(|(idx, ((height, time), (fast, slow)))| EpochPrediction {
+                epoch: Epoch(self.epoch.0 + idx as u64 + 1),
+                height,
+                time,
+                height_range: (fast.0.min(slow.0), fast.0.max(slow.0)),
+                time_range: (fast.1.min(slow.1), fast.1.max(slow.1)),
+            })
+            .collect()
+    }
+}
+
+pub async fn query_epoch_schedule(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+) -> Result<EpochSchedule, String> {
+    let epoch = rpc::query_epoch(&sdk.client)
+        .await
+        .map_err(|e| format!("Query epoch error: {}", e))?;
+    let (first_height, epoch_duration) = rpc::query_next_epoch_info(&sdk.client)
+        .await
+        .map_err(|e| format!("Query next epoch info error: {}", e))?;
+    let first_header = rpc::query_block_header(&sdk.client, first_height)
+        .await
+        .map_err(|e| format!("Query block header error: {}", e))?
+        .ok_or("First block of the epoch not found")?;
+
+    Ok(EpochSchedule {
+        epoch,
+        first_height: first_height.0,
+        first_time: first_header.time.to_unix_timestamp(),
+        min_num_of_blocks: epoch_duration.min_num_of_blocks,
+        min_duration: epoch_duration.min_duration.0 as i64,
+    })
+}
+
+pub async fn query_last_block(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+) -> Result<LastBlock, String> {
+    rpc::query_block(&sdk.client)
+        .await
+        .map_err(|e| format!("Query block error: {}", e))?
+        .ok_or_else(|| "No block committed yet".to_string())
+}
+
+/// Block times over the `blocks` blocks up to `last_height`. Only the blocks
+/// after the last missing header (e.g. pruned by the node) are used.
+pub async fn sample_block_times(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    last_height: u64,
+    blocks: u64,
+) -> Result<BlockTimes, String> {
+    let from = last_height.saturating_sub(blocks).max(1);
+    let mut timestamps = Vec::new();
+    for height in from..=last_height {
+        match rpc::query_block_header(&sdk.client, BlockHeight(height))
+            .await
+            .map_err(|e| format!("Query block header error: {}", e))?
+        {
+            Some(header) => timestamps.push(header.time.to_unix_timestamp()),
+            None => timestamps.clear(),
+        }
+    }
+    BlockTimes::from_timestamps(&timestamps)
+        .ok_or_else(|| "Need at least two blocks to measure block times".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule() -> EpochSchedule {
+        EpochSchedule {
+            epoch: Epoch(10),
+            first_height: 1000,
+            first_time: 0,
+            min_num_of_blocks: 100,
+            min_duration: 600,
+        }
+    }
+
+    #[test]
+    fn test_predict_epochs() {
+        assert!(BlockTimes::from_timestamps(&[5]).is_none());
+        let block_times = BlockTimes::from_timestamps(&[0, 5, 11, 15, 20]).unwrap();
+        assert_eq!(block_times.mean, 5.0);
+        assert_eq!(block_times.samples, 4);
+
+        // Time bound: at 5s per block, 600s take 120 blocks
+        let predictions = schedule().predict(1050, 250, &block_times, 2);
+        assert_eq!(predictions[0].epoch, Epoch(11));
+        assert_eq!(predictions[0].height, 1122);
+        assert_eq!(predictions[0].time, 610);
+        assert_eq!(predictions[1].height, 1244);
+        assert!(predictions[0].height_range.0 < 1122 && 1122 < predictions[0].height_range.1);
+
+        // The time condition was met but the block condition wasn't: blocks
+        // were slow, so only the remaining blocks count
+        let schedule = schedule();
+        assert!(schedule.time_condition_met(700));
+        assert!(!schedule.block_condition_met(1090));
+        let predictions = schedule.predict(1090, 700, &block_times, 1);
+        assert_eq!(predictions[0].height, 1102);
+        assert_eq!(predictions[0].time, 760);
+        assert_eq!(predictions[0].height_range, (1102, 1102));
+
+        // Both conditions met and the switch is already queued
+        let predictions = schedule.predict(1101, 900, &block_times, 1);
+        assert_eq!(predictions[0].height, 1102);
+    }
+}
@@ -0,0 +1,227 @@
+use std::collections::BTreeMap;
+
+use namada_core::token;
+use namada_parameters::storage::{get_gas_cost_key, get_max_block_gas_key};
+use namada_sdk::address::Address;
+use namada_sdk::io::NullIo;
+use namada_sdk::key::common;
+use namada_sdk::masp::fs::FsShieldedUtils;
+use namada_sdk::signing::SigningTxData;
+use namada_sdk::tendermint_rpc::HttpClient;
+use namada_sdk::tx::Tx;
+use namada_sdk::wallet::fs::FsWalletUtils;
+use namada_sdk::{args, rpc, NamadaImpl};
+
+use crate::multisig::{sign_tx, SignatureFile};
+use crate::registry::TokenRegistry;
+
+/// Gas limit used when the limit is neither given nor estimated
+pub const DEFAULT_GAS_LIMIT: u64 = 50_000;
+
+/// Minimum gas price of every token accepted for fees, per gas unit
+pub async fn query_minimum_gas_prices(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+) -> BTreeMap<Address, token::Amount> {
+    rpc::query_storage_value(&sdk.client, &get_gas_cost_key())
+        .await
+        .expect("Query minimum gas prices error")
+}
+
+/// The max gas of a block, the upper bound for any single tx
+pub async fn query_max_block_gas(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+) -> u64 {
+    rpc::query_storage_value(&sdk.client, &get_max_block_gas_key())
+        .await
+        .expect("Query max block gas error")
+}
+
+/// The max block gas, capped to the gas the fee payer can afford at `price`
+/// with what the tx leaves of its `balance` after sending `spent`
+pub fn affordable_gas_limit(
+    balance: token::Amount,
+    spent: token::Amount,
+    price: token::Amount,
+    max_block_gas: u64,
+) -> u64 {
+    let balance = balance.checked_sub(spent).unwrap_or_default();
+    match balance.checked_div(price) {
+        Some(gas) => u128::try_from(gas)
+            .ok()
+            .and_then(|gas| u64::try_from(gas).ok())
+            .map_or(max_block_gas, |gas| gas.min(max_block_gas)),
+        // Gas is free
+        None => max_block_gas,
+    }
+}
+
+/// Gas limit to dry-run a tx with. The fee of the wrapper is checked against
+/// the fee payer's balance, so this is the max block gas only if the payer
+/// can afford it at the minimum gas price. The tx sends `amount` of `token`
+/// from `source`, which the fee can't use if it is the payer's fee token.
+pub async fn dry_run_gas_limit(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    fee_payer: &common::PublicKey,
+    fee_token: &Address,
+    source: &Address,
+    token: &Address,
+    amount: token::Amount,
+) -> u64 {
+    let max_block_gas = query_max_block_gas(sdk).await;
+    let Some(price) = query_minimum_gas_prices(sdk).await.get(fee_token).copied() else {
+        return max_block_gas;
+    };
+    let payer = Address::from(fee_payer);
+    let balance = rpc::get_token_balance(&sdk.client, fee_token, &payer, None)
+        .await
+        .expect("Query fee payer balance error");
+    let spent = if source == &payer && token == fee_token {
+        amount
+    } else {
+        token::Amount::zero()
+    };
+    affordable_gas_limit(balance, spent, price, max_block_gas)
+}
+
+/// Dry-run the signed tx against the node and return the gas it consumed,
+/// or `None` if any inner tx failed (the gas used is meaningless then).
+pub async fn dry_run_gas_used(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    tx: &Tx,
+) -> Option<u64> {
+    let result = rpc::dry_run_tx(sdk, tx.to_bytes())
+        .await
+        .expect("Dry-run error");
+    let all_ok = result
+        .0
+        .iter()
+        .all(|(_, res)| matches!(res, Ok(res) if res.is_accepted()));
+    all_ok.then(|| u64::from(result.1))
+}
+
+/// Pad the measured gas usage by `margin_percent`, rounding up
+pub fn gas_limit_with_margin(gas_used: u64, margin_percent: u64) -> u64 {
+    let padded = (gas_used as u128 * (100 + margin_percent as u128)).div_ceil(100);
+    u64::try_from(padded).unwrap_or(u64::MAX)
+}
+
+/// Sign a tx built with enough gas (see [`dry_run_gas_limit`]) and dry-run it.
+/// Returns the gas used and the gas limit padded by `margin_percent`, or
+/// `None` if the dry-run failed.
+pub async fn estimate_gas_limit(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    mut tx: Tx,
+    tx_args: &args::Tx,
+    signing_data: SigningTxData,
+    signature_files: &[SignatureFile],
+    margin_percent: u64,
+) -> Option<(u64, u64)> {
+    sign_tx(sdk, &mut tx, tx_args, signing_data, signature_files).await;
+    let gas_used = dry_run_gas_used(sdk, &tx).await?;
+    Some((gas_used, gas_limit_with_margin(gas_used, margin_percent)))
+}
+
+/// An amount of the fee token, with its registry symbol if it has one
+async fn format_fee(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    registry: &TokenRegistry,
+    fee_token: &Address,
+    amount: token::Amount,
+) -> String {
+    match registry.get(fee_token) {
+        Some(_) => registry.format_amount(fee_token, amount),
+        None => format!(
+            "{} {}",
+            rpc::format_denominated_amount(&sdk.client, &NullIo, fee_token, amount).await,
+            fee_token
+        ),
+    }
+}
+
+/// Print the fee a tx with this gas limit costs in the given fee token
+pub async fn print_fee_estimate(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    registry: &TokenRegistry,
+    fee_token: &Address,
+    gas_used: u64,
+    gas_limit: u64,
+) {
+    let gas_prices = query_minimum_gas_prices(sdk).await;
+    println!("Estimated gas used: {}", gas_used);
+    println!("Gas limit with margin: {}", gas_limit);
+    match gas_prices.get(fee_token) {
+        Some(price) => {
+            let max_fee = price.checked_mul(gas_limit).unwrap();
+            println!(
+                "Minimum gas price: {} per gas unit",
+                format_fee(sdk, registry, fee_token, *price).await
+            );
+            println!(
+                "Max fee: {}",
+                format_fee(sdk, registry, fee_token, max_fee).await
+            );
+        }
+        None => println!(
+            "{} is not accepted as a fee token",
+            registry
+                .get(fee_token)
+                .map_or(fee_token.to_string(), |token| token.symbol.clone())
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gas_limit_with_margin() {
+        assert_eq!(gas_limit_with_margin(0, 20), 0);
+        assert_eq!(gas_limit_with_margin(50_000, 20), 60_000);
+        assert_eq!(gas_limit_with_margin(33_333, 10), 36_667);
+        assert_eq!(gas_limit_with_margin(u64::MAX, 50), u64::MAX);
+    }
+
+    #[test]
+    fn test_affordable_gas_limit() {
+        let price = token::Amount::from(10u64);
+        let zero = token::Amount::zero();
+        assert_eq!(
+            affordable_gas_limit(token::Amount::from(5_000u64), zero, price, 1_000),
+            500
+        );
+        assert_eq!(
+            affordable_gas_limit(token::Amount::from(50_000u64), zero, price, 1_000),
+            1_000
+        );
+        assert_eq!(
+            affordable_gas_limit(token::Amount::max(), zero, price, 1_000),
+            1_000
+        );
+        assert_eq!(affordable_gas_limit(zero, zero, zero, 1_000), 1_000);
+    }
+
+    #[test]
+    fn test_affordable_gas_limit_after_transfer() {
+        let price = token::Amount::from(10u64);
+        // The fee can only use what the transfer leaves of the balance
+        assert_eq!(
+            affordable_gas_limit(
+                token::Amount::from(50_000u64),
+                token::Amount::from(45_000u64),
+                price,
+                1_000
+            ),
+            500
+        );
+        assert_eq!(
+            affordable_gas_limit(
+                token::Amount::from(5_000u64),
+                token::Amount::from(6_000u64),
+                price,
+                1_000
+            ),
+            0
+        );
+    }
+}
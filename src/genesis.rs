@@ -0,0 +1,340 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use namada_core::token::{self, NATIVE_MAX_DECIMAL_PLACES};
+use namada_sdk::address::Address;
+use namada_sdk::key::common;
+use serde::Deserialize;
+
+use crate::address_book::AddressBook;
+use crate::loaders::{parse_address, Record};
+use crate::{
+    get_addresses_from_file, get_backer_balance, get_core_balance, get_full_path,
+    get_future_alloc_balance, get_genesis_accounts, get_pg_validator_balance,
+    get_public_alloc_balance, get_rd_balance,
+};
+
+/// Where the accounts of a category are listed today
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CategorySource {
+    /// An address list, e.g. `config/backers.txt`
+    List(&'static str),
+    /// A file of genesis records, e.g. `config/rd_ecosystem_dev.json`
+    Records(&'static str),
+    /// The genesis validator accounts
+    Validators,
+    /// Every account in no other category
+    Remainder,
+}
+
+/// A genesis allocation category
+#[derive(Debug, Clone, Copy)]
+pub struct Category {
+    /// `Record.category` and the stem of its files
+    pub key: &'static str,
+    pub title: &'static str,
+    pub source: CategorySource,
+    /// The genesis balance the crate uses for the category
+    pub genesis_balance: fn() -> token::Amount,
+}
+
+impl Category {
+    /// File name stem, e.g. `rd_ecosystem_dev` for `r&d_ecosystem_dev`
+    pub fn file_stem(&self) -> &'static str {
+        match self.source {
+            CategorySource::List(path) | CategorySource::Records(path) => Path::new(path)
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap(),
+            CategorySource::Validators | CategorySource::Remainder => self.key,
+        }
+    }
+}
+
+/// The allocation categories, in the order accounts are matched
+pub const CATEGORIES: [Category; 6] = [
+    Category {
+        key: "backers",
+        title: "Backers",
+        source: CategorySource::List("config/backers.txt"),
+        genesis_balance: get_backer_balance,
+    },
+    Category {
+        key: "core_team",
+        title: "Core team",
+        source: CategorySource::List("config/core_team.txt"),
+        genesis_balance: get_core_balance,
+    },
+    Category {
+        key: "r&d_ecosystem_dev",
+        title: "R&D ecosystems",
+        source: CategorySource::Records("config/rd_ecosystem_dev.json"),
+        genesis_balance: get_rd_balance,
+    },
+    Category {
+        key: "public_allocations_future",
+        title: "Future allocations",
+        source: CategorySource::Records("config/public_allocations_future.json"),
+        genesis_balance: get_future_alloc_balance,
+    },
+    Category {
+        key: "pg_validators",
+        title: "PG validators",
+        source: CategorySource::Validators,
+        genesis_balance: get_pg_validator_balance,
+    },
+    Category {
+        key: "public_allocations",
+        title: "Public allocations",
+        source: CategorySource::Remainder,
+        genesis_balance: get_public_alloc_balance,
+    },
+];
+
+/// An account of the genesis files with its native token balance and the
+/// amount it bonds at genesis
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenesisAccount {
+    pub address: Address,
+    pub balance: token::Amount,
+    pub bonded: token::Amount,
+    pub validator: bool,
+}
+
+#[derive(Deserialize)]
+struct BalancesFile {
+    #[serde(default)]
+    token: BTreeMap<String, BTreeMap<String, toml::Value>>,
+}
+
+#[derive(Deserialize)]
+struct TransactionsFile {
+    #[serde(default)]
+    validator_account: Vec<ValidatorAccountTx>,
+    #[serde(default)]
+    bond: Vec<BondTx>,
+}
+
+#[derive(Deserialize)]
+struct ValidatorAccountTx {
+    address: String,
+}
+
+#[derive(Deserialize)]
+struct BondTx {
+    source: String,
+    amount: toml::Value,
+}
+
+/// An amount in whole tokens with up to 6 decimal places, as in the genesis
+/// files
+fn native_amount(value: &toml::Value) -> Result<token::Amount, String> {
+    let raw = match value {
+        toml::Value::String(raw) => raw.clone(),
+        toml::Value::Integer(raw) => raw.to_string(),
+        other => return Err(format!("invalid amount {}", other)),
+    };
+    token::Amount::from_str(&raw, NATIVE_MAX_DECIMAL_PLACES)
+        .map_err(|e| format!("invalid amount {}: {}", raw, e))
+}
+
+/// A balance owner or bond source, either an address or the public key of an
+/// implicit account
+fn bond_source(raw: &str) -> Result<Address, String> {
+    match common::PublicKey::from_str(raw) {
+        Ok(pk) => Ok(Address::from(&pk)),
+        Err(_) => parse_address(raw),
+    }
+}
+
+/// The accounts with a balance of `token` (the alias in `balances.toml`,
+/// e.g. `NAM`) and their genesis bonds
+pub fn parse_genesis(
+    balances: &str,
+    transactions: &str,
+    token: &str,
+) -> Result<Vec<GenesisAccount>, String> {
+    let balances: BalancesFile =
+        toml::from_str(balances).map_err(|e| format!("balances file: {}", e))?;
+    let transactions: TransactionsFile =
+        toml::from_str(transactions).map_err(|e| format!("transactions file: {}", e))?;
+
+    let (_, token_balances) = balances
+        .token
+        .iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(token))
+        .ok_or_else(|| format!("No {} balances in the balances file", token))?;
+    let mut accounts = token_balances
+        .iter()
+        .map(|(address, balance)| {
+            Ok(GenesisAccount {
+                address: bond_source(address)?,
+                balance: native_amount(balance)?,
+                bonded: token::Amount::zero(),
+                validator: false,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    for validator in &transactions.validator_account {
+        let address = parse_address(&validator.address)?;
+        if let Some(account) = accounts
+            .iter_mut()
+            .find(|account| account.address == address)
+        {
+            account.validator = true;
+        }
+    }
+    for bond in &transactions.bond {
+        let source = bond_source(&bond.source)?;
+        let amount = native_amount(&bond.amount)?;
+        let account = accounts
+            .iter_mut()
+            .find(|account| account.address == source)
+            .ok_or_else(|| format!("Bond from {} which has no genesis balance", source))?;
+        account.bonded = account
+            .bonded
+            .checked_add(amount)
+            .ok_or_else(|| String::from("Bonded amount overflow"))?;
+    }
+    Ok(accounts)
+}
+
+/// The category and name of the accounts in the crate's category files and
+/// the address book groups. Missing files are skipped.
+pub fn known_categories(book: &AddressBook) -> BTreeMap<Address, (&'static str, String)> {
+    let mut known = BTreeMap::new();
+    for category in &CATEGORIES {
+        let mut add = |address: Address, name: String| {
+            known.entry(address).or_insert((category.key, name));
+        };
+        match category.source {
+            // The address list already holds the address book group
+            CategorySource::List(path) if Path::new(&get_full_path(path)).exists() => {
+                for address in get_addresses_from_file(book, path) {
+                    let name = book.label(&address).unwrap_or_default().to_string();
+                    add(address, name);
+                }
+                continue;
+            }
+            CategorySource::Records(path) if Path::new(&get_full_path(path)).exists() => {
+                for record in get_genesis_accounts(book, path) {
+                    add(record.address, record.name);
+                }
+            }
+            _ => {}
+        }
+        for address in book.group(category.file_stem()) {
+            let name = book.label(&address).unwrap_or_default().to_string();
+            add(address, name);
+        }
+    }
+    known
+}
+
+/// The genesis accounts as records by category key. Known accounts keep
+/// their category, other validators are PG validators and the rest are
+/// public allocations.
+pub fn categorize(
+    accounts: &[GenesisAccount],
+    known: &BTreeMap<Address, (&'static str, String)>,
+) -> BTreeMap<&'static str, Vec<Record>> {
+    let mut records: BTreeMap<&'static str, Vec<Record>> = BTreeMap::new();
+    for account in accounts {
+        let (category, name) = match known.get(&account.address) {
+            Some((category, name)) => (*category, name.clone()),
+            None => {
+                let source = if account.validator {
+                    CategorySource::Validators
+                } else {
+                    CategorySource::Remainder
+                };
+                let category = CATEGORIES
+                    .iter()
+                    .find(|category| category.source == source)
+                    .unwrap();
+                (category.key, String::new())
+            }
+        };
+        records.entry(category).or_default().push(Record {
+            address: account.address.clone(),
+            amount: account.balance,
+            category: category.to_string(),
+            name,
+        });
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use namada_core::address::gen_deterministic_established_address;
+    use namada_sdk::key::{ed25519, RefTo, SigScheme};
+    use rand_core::OsRng;
+
+    use super::*;
+
+    #[test]
+    fn test_import_genesis() {
+        let backer = gen_deterministic_established_address("backer");
+        let validator = gen_deterministic_established_address("validator");
+        let public = gen_deterministic_established_address("public");
+        let balances = format!(
+            "[token.NAM]\n{} = \"1000.5\"\n{} = \"205\"\n{} = \"42\"\n\n[token.OSMO]\n{} = \"7\"\n",
+            backer, validator, public, backer
+        );
+        let transactions = format!(
+            "[[validator_account]]\naddress = \"{}\"\nvp = \"vp_user\"\n\n\
+             [[bond]]\nsource = \"{}\"\nvalidator = \"{}\"\namount = \"100\"\n\n\
+             [[bond]]\nsource = \"{}\"\nvalidator = \"{}\"\namount = \"0.5\"\n",
+            validator, backer, validator, backer, validator
+        );
+
+        let accounts = parse_genesis(&balances, &transactions, "nam").unwrap();
+        assert_eq!(accounts.len(), 3);
+        let account = |address: &Address| {
+            accounts
+                .iter()
+                .find(|account| &account.address == address)
+                .unwrap()
+        };
+        assert_eq!(
+            account(&backer).balance,
+            token::Amount::from(1_000_500_000u64)
+        );
+        assert_eq!(account(&backer).bonded, token::Amount::from(100_500_000u64));
+        assert!(account(&validator).validator);
+
+        let mut known = BTreeMap::new();
+        known.insert(backer.clone(), ("backers", String::from("Backer")));
+        let records = categorize(&accounts, &known);
+        assert_eq!(records["backers"][0].name, "Backer");
+        assert_eq!(records["pg_validators"][0].address, validator);
+        assert_eq!(records["public_allocations"][0].address, public);
+        assert_eq!(
+            records["public_allocations"][0].amount,
+            token::Amount::native_whole(42)
+        );
+
+        // A bond from an account without a balance is an error
+        let stray = gen_deterministic_established_address("stray");
+        let transactions = format!(
+            "[[bond]]\nsource = \"{}\"\nvalidator = \"{}\"\namount = \"1\"\n",
+            stray, validator
+        );
+        assert!(parse_genesis(&balances, &transactions, "NAM").is_err());
+
+        // An implicit account may be listed by its public key
+        let sk = common::SecretKey::Ed25519(ed25519::SigScheme::generate(&mut OsRng));
+        let pk = sk.ref_to();
+        let balances = format!("[token.NAM]\n{} = \"10\"\n", pk);
+        let transactions = format!(
+            "[[bond]]\nsource = \"{}\"\nvalidator = \"{}\"\namount = \"4\"\n",
+            pk, validator
+        );
+        let accounts = parse_genesis(&balances, &transactions, "NAM").unwrap();
+        assert_eq!(accounts[0].address, Address::from(&pk));
+        assert_eq!(accounts[0].bonded, token::Amount::native_whole(4));
+    }
+}
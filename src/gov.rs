@@ -0,0 +1,446 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use namada_core::chain::Epoch;
+use namada_core::ibc::core::host::types::identifiers::{ChannelId, PortId};
+use namada_core::ibc::PGFIbcTarget;
+use namada_core::token;
+use namada_governance::cli::onchain::{
+    DefaultProposal, OnChainProposal, PgfFunding, PgfFundingProposal, PgfStewardProposal,
+    StewardsUpdate,
+};
+use namada_governance::cli::validation::{
+    is_valid_activation_epoch, is_valid_author_balance, is_valid_content,
+    is_valid_default_proposal_data, is_valid_end_epoch, is_valid_pgf_funding_data,
+    is_valid_pgf_stewards_data, is_valid_proposal_period, is_valid_start_epoch,
+};
+use namada_governance::parameters::GovernanceParameters;
+use namada_governance::storage::keys::get_counter_key;
+use namada_governance::storage::proposal::{PGFInternalTarget, PGFTarget, StorageProposal};
+use namada_governance::utils::{ProposalResult, TallyType, Vote};
+use namada_governance::ProposalVote;
+use namada_sdk::address::Address;
+use namada_sdk::io::NullIo;
+use namada_sdk::masp::fs::FsShieldedUtils;
+use namada_sdk::tendermint_rpc::HttpClient;
+use namada_sdk::wallet::fs::FsWalletUtils;
+use namada_sdk::{rpc, NamadaImpl};
+use namada_token::Dec;
+
+/// Yay, nay and abstain voting power
+#[derive(Debug, Clone, Default)]
+pub struct VoteSplit {
+    pub yay: token::Amount,
+    pub nay: token::Amount,
+    pub abstain: token::Amount,
+}
+
+impl VoteSplit {
+    pub fn add(&mut self, vote: &ProposalVote, power: token::Amount) {
+        let side = match vote {
+            ProposalVote::Yay => &mut self.yay,
+            ProposalVote::Nay => &mut self.nay,
+            ProposalVote::Abstain => &mut self.abstain,
+        };
+        *side = side.checked_add(power).unwrap();
+    }
+
+    pub fn total(&self) -> token::Amount {
+        self.yay
+            .checked_add(self.nay)
+            .and_then(|sum| sum.checked_add(self.abstain))
+            .unwrap()
+    }
+}
+
+/// The votes on a proposal, split by who cast them, and the resulting tally
+#[derive(Debug, Clone)]
+pub struct ProposalTally {
+    pub votes: Vec<Vote>,
+    /// Stake of the validators that voted, before delegator overrides
+    pub validators: VoteSplit,
+    /// Bonds of the delegators that voted, overriding their validators' votes
+    pub delegators: VoteSplit,
+    /// The stored result once the proposal ended, the current tally otherwise.
+    /// `None` before voting starts.
+    pub result: Option<ProposalResult>,
+}
+
+/// Ids are assigned from a counter, so every proposal below it exists
+pub async fn query_proposals(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+) -> Vec<StorageProposal> {
+    let counter: u64 = rpc::query_storage_value(&sdk.client, &get_counter_key())
+        .await
+        .expect("Query proposal counter error");
+    let mut proposals = Vec::new();
+    for id in 0..counter {
+        if let Some(proposal) = query_proposal(sdk, id).await {
+            proposals.push(proposal);
+        }
+    }
+    proposals
+}
+
+pub async fn query_proposal(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    id: u64,
+) -> Option<StorageProposal> {
+    rpc::query_proposal_by_id(&sdk.client, id)
+        .await
+        .expect("Query proposal error")
+}
+
+/// Voting power is taken at the current epoch while voting is open and at the
+/// end of voting afterwards
+pub async fn query_proposal_tally(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    proposal: &StorageProposal,
+) -> ProposalTally {
+    let current_epoch = rpc::query_epoch(&sdk.client)
+        .await
+        .expect("Query epoch error");
+    let tally_epoch: Epoch = current_epoch.min(proposal.voting_end_epoch);
+
+    let votes = rpc::query_proposal_votes(&sdk.client, proposal.id)
+        .await
+        .expect("Query proposal votes error");
+
+    let mut validators = VoteSplit::default();
+    let mut delegators = VoteSplit::default();
+    for vote in &votes {
+        if vote.is_validator() {
+            let stake = rpc::get_validator_stake(&sdk.client, tally_epoch, &vote.validator)
+                .await
+                .unwrap_or_default();
+            validators.add(&vote.data, stake);
+        } else {
+            let bond =
+                rpc::get_bond_amount_at(&sdk.client, &vote.delegator, &vote.validator, tally_epoch)
+                    .await
+                    .unwrap_or_default();
+            delegators.add(&vote.data, bond);
+        }
+    }
+
+    let result = rpc::query_proposal_result(&sdk.client, proposal.id)
+        .await
+        .expect("Query proposal result error");
+
+    ProposalTally {
+        votes,
+        validators,
+        delegators,
+        result,
+    }
+}
+
+/// Stake of a validator, or the bonds of a delegator, at the given epoch.
+/// Returns whether the voter is a validator along with its voting power.
+pub async fn query_voting_power(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    voter: &Address,
+    epoch: Epoch,
+) -> (bool, token::Amount) {
+    let is_validator = rpc::is_validator(&sdk.client, voter)
+        .await
+        .expect("Query validator error");
+    if is_validator {
+        let stake = rpc::get_validator_stake(&sdk.client, epoch, voter)
+            .await
+            .unwrap_or_default();
+        return (true, stake);
+    }
+
+    let validators = rpc::get_delegation_validators(&sdk.client, voter, epoch)
+        .await
+        .expect("Query delegations error");
+    let mut power = token::Amount::zero();
+    for validator in validators {
+        let bond = rpc::get_bond_amount_at(&sdk.client, voter, &validator, epoch)
+            .await
+            .unwrap_or_default();
+        power = power.checked_add(bond).unwrap();
+    }
+    (false, power)
+}
+
+/// Fraction of the total voting power that must vote for the tally to count.
+/// For PGF fundings proposed by a steward it is instead the turnout needed to
+/// be able to reject the proposal.
+pub fn quorum_threshold(tally_type: &TallyType) -> Dec {
+    match tally_type {
+        TallyType::TwoFifths => Dec::two_fifths(),
+        TallyType::OneHalfOverOneThird | TallyType::LessOneHalfOverOneThirdNay => Dec::one_third(),
+    }
+}
+
+/// Fraction of the total voting power that voted
+pub fn turnout(result: &ProposalResult) -> Dec {
+    if result.total_voting_power.is_zero() {
+        return Dec::zero();
+    }
+    let voted = result
+        .total_yay_power
+        .checked_add(result.total_nay_power)
+        .and_then(|sum| sum.checked_add(result.total_abstain_power))
+        .unwrap();
+    Dec::try_from(voted)
+        .unwrap()
+        .checked_div(Dec::try_from(result.total_voting_power).unwrap())
+        .unwrap()
+}
+
+/// Content fields of a proposal, as used by the proposals on Namada
+const CONTENT_KEYS: [&str; 9] = [
+    "title",
+    "authors",
+    "discussions-to",
+    "created",
+    "license",
+    "abstract",
+    "motivation",
+    "details",
+    "requires",
+];
+
+/// Content fields that must not be left empty
+const REQUIRED_CONTENT_KEYS: [&str; 5] = ["title", "authors", "abstract", "motivation", "details"];
+
+/// A proposal JSON as submitted with `namadac init-proposal`
+#[derive(Debug, Clone)]
+pub enum ProposalDraft {
+    Default(DefaultProposal),
+    PgfSteward(PgfStewardProposal),
+    PgfFunding(PgfFundingProposal),
+}
+
+impl ProposalDraft {
+    /// Skeleton of a proposal by `author`, with the earliest epochs allowed by
+    /// the governance parameters and empty content
+    pub fn skeleton(
+        kind: &str,
+        author: &Address,
+        current_epoch: Epoch,
+        params: &GovernanceParameters,
+    ) -> Self {
+        let start = current_epoch.0 + 2.min(params.max_proposal_latency).max(1);
+        let end = start + params.min_proposal_voting_period.max(1);
+        let activation = end + params.min_proposal_grace_epochs.max(1);
+        let proposal = OnChainProposal {
+            content: CONTENT_KEYS
+                .iter()
+                .map(|key| (key.to_string(), String::new()))
+                .collect::<BTreeMap<_, _>>(),
+            author: author.clone(),
+            voting_start_epoch: Epoch(start),
+            voting_end_epoch: Epoch(end),
+            activation_epoch: Epoch(activation),
+        };
+        match kind {
+            "pgf-steward" => Self::PgfSteward(PgfStewardProposal {
+                proposal,
+                data: StewardsUpdate {
+                    add: Some(author.clone()),
+                    remove: vec![],
+                },
+            }),
+            "pgf-funding" => Self::PgfFunding(PgfFundingProposal {
+                proposal,
+                data: PgfFunding {
+                    continuous: vec![
+                        PGFTarget::Internal(PGFInternalTarget {
+                            target: author.clone(),
+                            amount: token::Amount::zero(),
+                        }),
+                        PGFTarget::Ibc(PGFIbcTarget {
+                            target: String::from("osmo1.."),
+                            amount: token::Amount::zero(),
+                            port_id: PortId::transfer(),
+                            channel_id: ChannelId::from_str("channel-1").unwrap(),
+                        }),
+                    ],
+                    retro: vec![PGFTarget::Internal(PGFInternalTarget {
+                        target: author.clone(),
+                        amount: token::Amount::zero(),
+                    })],
+                },
+            }),
+            _ => Self::Default(DefaultProposal {
+                proposal,
+                data: None,
+            }),
+        }
+    }
+
+    /// The kind of proposal is told apart by the shape of its `data`
+    pub fn parse(json: &str) -> Result<Self, serde_json::Error> {
+        let value: serde_json::Value = serde_json::from_str(json)?;
+        let data = value.get("data");
+        if data.is_some_and(|data| data.get("continuous").is_some() || data.get("retro").is_some())
+        {
+            serde_json::from_value(value).map(Self::PgfFunding)
+        } else if data.is_some_and(|data| data.get("add").is_some() || data.get("remove").is_some())
+        {
+            serde_json::from_value(value).map(Self::PgfSteward)
+        } else {
+            serde_json::from_value(value).map(Self::Default)
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        match self {
+            Self::Default(draft) => serde_json::to_string_pretty(draft),
+            Self::PgfSteward(draft) => serde_json::to_string_pretty(draft),
+            Self::PgfFunding(draft) => serde_json::to_string_pretty(draft),
+        }
+        .unwrap()
+    }
+
+    pub fn proposal(&self) -> &OnChainProposal {
+        match self {
+            Self::Default(draft) => &draft.proposal,
+            Self::PgfSteward(draft) => &draft.proposal,
+            Self::PgfFunding(draft) => &draft.proposal,
+        }
+    }
+
+    /// Every rule of the governance parameters that the draft breaks. Unlike
+    /// the checks of `namadac init-proposal`, this doesn't stop at the first
+    /// violation.
+    pub fn violations(
+        &self,
+        params: &GovernanceParameters,
+        current_epoch: Epoch,
+        author_balance: token::Amount,
+    ) -> Vec<String> {
+        let proposal = self.proposal();
+        let start = proposal.voting_start_epoch;
+        let end = proposal.voting_end_epoch;
+        let activation = proposal.activation_epoch;
+
+        let mut checks = vec![
+            is_valid_author_balance(author_balance, params.min_proposal_fund),
+            is_valid_content(&proposal.content, params.max_proposal_content_size),
+            is_valid_start_epoch(start, current_epoch),
+        ];
+        if end > start {
+            checks.push(is_valid_end_epoch(
+                start,
+                end,
+                current_epoch,
+                params.min_proposal_voting_period,
+                params.max_proposal_period,
+            ));
+        }
+        if activation > end {
+            checks.push(is_valid_activation_epoch(
+                activation,
+                end,
+                params.min_proposal_grace_epochs,
+            ));
+        }
+        if activation > start {
+            checks.push(is_valid_proposal_period(
+                start,
+                activation,
+                params.max_proposal_period,
+            ));
+        }
+        checks.push(match self {
+            Self::Default(draft) => {
+                is_valid_default_proposal_data(&draft.data, params.max_proposal_code_size)
+            }
+            Self::PgfSteward(draft) => is_valid_pgf_stewards_data(&draft.data, &proposal.author),
+            Self::PgfFunding(draft) => is_valid_pgf_funding_data(&draft.data),
+        });
+
+        let mut violations: Vec<String> = checks
+            .into_iter()
+            .filter_map(|check| check.err().map(|err| err.to_string()))
+            .collect();
+        if end <= start {
+            violations.push(format!(
+                "Invalid proposal end epoch: voting ends at epoch {} but starts at epoch {}",
+                end, start
+            ));
+        }
+        if activation <= end {
+            violations.push(format!(
+                "Invalid proposal activation epoch: activation at epoch {} must be after the \
+                 voting end epoch {}",
+                activation, end
+            ));
+        }
+        if start.0.saturating_sub(current_epoch.0) > params.max_proposal_latency {
+            violations.push(format!(
+                "Invalid proposal start epoch: voting must start at most {} epochs after the \
+                 current epoch {}",
+                params.max_proposal_latency, current_epoch
+            ));
+        }
+        for key in REQUIRED_CONTENT_KEYS {
+            if proposal
+                .content
+                .get(key)
+                .is_none_or(|value| value.is_empty())
+            {
+                violations.push(format!("Missing proposal content: {}", key));
+            }
+        }
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use namada_governance::utils::TallyResult;
+
+    use super::*;
+
+    #[test]
+    fn test_vote_split_and_turnout() {
+        let mut split = VoteSplit::default();
+        split.add(&ProposalVote::Yay, token::Amount::from(30u64));
+        split.add(&ProposalVote::Nay, token::Amount::from(5u64));
+        split.add(&ProposalVote::Yay, token::Amount::from(10u64));
+        split.add(&ProposalVote::Abstain, token::Amount::from(5u64));
+        assert_eq!(split.yay, token::Amount::from(40u64));
+        assert_eq!(split.total(), token::Amount::from(50u64));
+
+        let result = ProposalResult {
+            result: TallyResult::Passed,
+            tally_type: TallyType::TwoFifths,
+            total_voting_power: token::Amount::from(100u64),
+            total_yay_power: split.yay,
+            total_nay_power: split.nay,
+            total_abstain_power: split.abstain,
+        };
+        assert_eq!(turnout(&result), Dec::new(5, 1).unwrap());
+        assert!(turnout(&result) >= quorum_threshold(&result.tally_type));
+    }
+
+    #[test]
+    fn test_draft_violations() {
+        let params = GovernanceParameters::default();
+        let author = namada_core::address::gen_deterministic_established_address("author");
+        let current_epoch = Epoch(10);
+
+        let mut draft = ProposalDraft::skeleton("pgf-steward", &author, current_epoch, &params);
+        let parsed = ProposalDraft::parse(&draft.to_json()).unwrap();
+        assert!(matches!(parsed, ProposalDraft::PgfSteward(_)));
+
+        // Only the content is left to fill in
+        let violations = parsed.violations(&params, current_epoch, params.min_proposal_fund);
+        assert_eq!(violations.len(), REQUIRED_CONTENT_KEYS.len());
+
+        if let ProposalDraft::PgfSteward(steward) = &mut draft {
+            for value in steward.proposal.content.values_mut() {
+                *value = String::from("x");
+            }
+            steward.proposal.activation_epoch = steward.proposal.voting_end_epoch;
+        }
+        let violations = draft.violations(&params, current_epoch, token::Amount::zero());
+        assert_eq!(violations.len(), 2);
+    }
+}
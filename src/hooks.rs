@@ -0,0 +1,52 @@
+use serde::Serialize;
+
+/// A change worth reacting to, passed to the hooks
+#[derive(Debug, Clone, Serialize)]
+pub struct Alert {
+    /// `epoch_change` or `staked_ratio`
+    pub kind: String,
+    pub message: String,
+    pub height: u64,
+    pub epoch: u64,
+    pub staked_ratio: String,
+}
+
+/// Where alerts are sent: a shell command, run with the alert in `ALERT_*`
+/// environment variables, and/or a webhook URL receiving it as JSON
+#[derive(Debug, Clone, Default)]
+pub struct Hooks {
+    pub command: Option<String>,
+    pub url: Option<String>,
+}
+
+impl Hooks {
+    /// Run every hook. Failures are reported but don't stop the caller.
+    pub async fn notify(&self, alert: &Alert) {
+        if let Some(command) = &self.command {
+            let status = tokio::process::Command::new("sh")
+                .arg("-c")
+                .arg(command)
+                .env("ALERT_KIND", &alert.kind)
+                .env("ALERT_MESSAGE", &alert.message)
+                .env("ALERT_HEIGHT", alert.height.to_string())
+                .env("ALERT_EPOCH", alert.epoch.to_string())
+                .env("ALERT_STAKED_RATIO", &alert.staked_ratio)
+                .status()
+                .await;
+            match status {
+                Ok(status) if status.success() => {}
+                Ok(status) => println!("Hook command exited with {}", status),
+                Err(e) => println!("Hook command failed: {}", e),
+            }
+        }
+
+        if let Some(url) = &self.url {
+            let response = reqwest::Client::new().post(url).json(alert).send().await;
+            match response {
+                Ok(response) if response.status().is_success() => {}
+                Ok(response) => println!("Webhook returned {}", response.status()),
+                Err(e) => println!("Webhook failed: {}", e),
+            }
+        }
+    }
+}
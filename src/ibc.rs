@@ -0,0 +1,394 @@
+use std::time::{Duration, Instant};
+
+use namada_core::ibc::clients::tendermint::types::{
+    ClientState as TmClientState, ConsensusState as TmConsensusState,
+};
+use namada_core::ibc::core::channel::types::channel::ChannelEnd;
+use namada_core::ibc::core::client::types::Height;
+use namada_core::ibc::core::connection::types::ConnectionEnd;
+use namada_core::ibc::core::host::types::identifiers::{
+    ChainId, ChannelId, ClientId, ConnectionId, PortId, Sequence,
+};
+use namada_core::ibc::primitives::proto::{Any, Protobuf};
+use namada_core::storage::PrefixValue;
+use namada_core::tendermint::Time;
+use namada_core::token;
+use namada_ibc::event::IbcEventType;
+use namada_ibc::storage;
+use namada_sdk::address::Address;
+use namada_sdk::error::{Error, QueryError};
+use namada_sdk::io::NullIo;
+use namada_sdk::masp::fs::FsShieldedUtils;
+use namada_sdk::queries::RPC;
+use namada_sdk::tendermint_rpc::HttpClient;
+use namada_sdk::tx::ProcessTxResponse;
+use namada_sdk::wallet::fs::FsWalletUtils;
+use namada_sdk::{rpc, NamadaImpl};
+
+/// Light client state of a counterparty chain, as tracked by Namada
+#[derive(Debug, Clone)]
+pub struct ClientInfo {
+    pub client_id: ClientId,
+    pub chain_id: ChainId,
+    pub latest_height: Height,
+    pub trusting_period: Duration,
+    pub frozen: bool,
+    /// Timestamp of the counterparty header at the latest height
+    pub latest_timestamp: Time,
+}
+
+impl ClientInfo {
+    /// The client expires if it isn't updated within the trusting period
+    /// of the latest header
+    pub fn expires_at(&self) -> Time {
+        self.latest_timestamp
+            .checked_add(self.trusting_period)
+            .expect("Client expiry overflows")
+    }
+
+    /// Time left before the client expires, or `None` if it already has
+    pub fn time_left(&self, now: Time) -> Option<Duration> {
+        self.expires_at().duration_since(now).ok()
+    }
+}
+
+/// Raw values of all the IBC storage keys under the given path
+async fn query_ibc_prefix(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    path: &str,
+) -> Vec<PrefixValue> {
+    let prefix = storage::ibc_key(path).expect("Invalid IBC storage path");
+    RPC.shell()
+        .storage_prefix(&sdk.client, None, None, false, &prefix)
+        .await
+        .expect("Query IBC storage error")
+        .data
+}
+
+async fn query_ibc_value(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    key: &namada_core::storage::Key,
+) -> Vec<u8> {
+    rpc::query_storage_value_bytes(&sdk.client, key, None, false)
+        .await
+        .expect("Query IBC storage error")
+        .0
+        .unwrap_or_else(|| panic!("No value in storage for {}", key))
+}
+
+/// All connection ends of Namada
+pub async fn query_connections(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+) -> Vec<(ConnectionId, ConnectionEnd)> {
+    query_ibc_prefix(sdk, "connections")
+        .await
+        .into_iter()
+        .filter_map(|PrefixValue { key, value }| {
+            // Skips the connection counter
+            let conn_id = storage::connection_id(&key).ok()?;
+            let end = ConnectionEnd::decode_vec(&value).expect("Could not decode connection");
+            Some((conn_id, end))
+        })
+        .collect()
+}
+
+/// All channel ends of Namada, on any port
+pub async fn query_channels(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+) -> Vec<(PortId, ChannelId, ChannelEnd)> {
+    query_ibc_prefix(sdk, "channelEnds")
+        .await
+        .into_iter()
+        .filter_map(|PrefixValue { key, value }| {
+            // Skips the channel counter
+            let (port_id, channel_id) = storage::port_channel_id(&key).ok()?;
+            let end = ChannelEnd::decode_vec(&value).expect("Could not decode channel");
+            Some((port_id, channel_id, end))
+        })
+        .collect()
+}
+
+pub async fn query_channel(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    port_id: &PortId,
+    channel_id: &ChannelId,
+) -> ChannelEnd {
+    let bytes = query_ibc_value(sdk, &storage::channel_key(port_id, channel_id)).await;
+    ChannelEnd::decode_vec(&bytes).expect("Could not decode channel")
+}
+
+/// The client state and the consensus state at its latest height
+pub async fn query_client(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    client_id: &ClientId,
+) -> ClientInfo {
+    let bytes = query_ibc_value(sdk, &storage::client_state_key(client_id)).await;
+    let client_state = <TmClientState as Protobuf<Any>>::decode_vec(&bytes)
+        .expect("Could not decode client state, only Tendermint clients are supported");
+
+    let bytes = query_ibc_value(
+        sdk,
+        &storage::consensus_state_key(client_id, client_state.latest_height),
+    )
+    .await;
+    let consensus_state = <TmConsensusState as Protobuf<Any>>::decode_vec(&bytes)
+        .expect("Could not decode consensus state");
+
+    ClientInfo {
+        client_id: client_id.clone(),
+        chain_id: client_state.chain_id.clone(),
+        latest_height: client_state.latest_height,
+        trusting_period: client_state.trusting_period,
+        frozen: client_state.is_frozen(),
+        latest_timestamp: consensus_state.timestamp,
+    }
+}
+
+/// Number of packets sent on the channel that were neither acknowledged nor
+/// timed out yet
+pub async fn query_pending_commitments(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    port_id: &PortId,
+    channel_id: &ChannelId,
+) -> usize {
+    let path = format!(
+        "commitments/ports/{}/channels/{}/sequences",
+        port_id, channel_id
+    );
+    query_ibc_prefix(sdk, &path).await.len()
+}
+
+/// A packet sent by an applied tx, identified by its source channel
+#[derive(Debug, Clone)]
+pub struct SentPacket {
+    pub port_id: PortId,
+    pub channel_id: ChannelId,
+    pub sequence: Sequence,
+}
+
+/// The packets sent by the tx, found from the packet commitments it wrote
+pub fn sent_packets(response: &ProcessTxResponse) -> Vec<SentPacket> {
+    let ProcessTxResponse::Applied(response) = response else {
+        return vec![];
+    };
+    let Some(batch) = &response.batch else {
+        return vec![];
+    };
+    batch
+        .0
+        .values()
+        .filter_map(|result| result.as_ref().ok())
+        .filter(|result| result.is_accepted())
+        .flat_map(|result| result.changed_keys.iter())
+        .filter_map(storage::is_ibc_commitment_key)
+        .map(|path| SentPacket {
+            port_id: path.port_id,
+            channel_id: path.channel_id,
+            sequence: path.sequence,
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketOutcome {
+    Acknowledged,
+    TimedOut,
+    /// The commitment was cleared, but the event is no longer in the
+    /// node's event log
+    Unknown,
+}
+
+/// Wait until the packet is acknowledged or times out, i.e. until its
+/// commitment is removed. Returns `None` if it is still pending after
+/// `max_wait`.
+pub async fn wait_for_packet(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    packet: &SentPacket,
+    poll_interval: Duration,
+    max_wait: Duration,
+) -> Option<PacketOutcome> {
+    let commitment_key =
+        storage::commitment_key(&packet.port_id, &packet.channel_id, packet.sequence);
+    let deadline = Instant::now() + max_wait;
+    loop {
+        let pending = rpc::query_has_storage_key(&sdk.client, &commitment_key)
+            .await
+            .expect("Query packet commitment error");
+        if !pending {
+            break;
+        }
+        if Instant::now() >= deadline {
+            return None;
+        }
+        tokio::time::sleep(poll_interval).await;
+    }
+
+    let channel = query_channel(sdk, &packet.port_id, &packet.channel_id).await;
+    let counterparty_port = channel.counterparty().port_id().clone();
+    let counterparty_channel = channel
+        .counterparty()
+        .channel_id()
+        .expect("Channel has no counterparty channel")
+        .clone();
+    for (event_type, outcome) in [
+        ("acknowledge_packet", PacketOutcome::Acknowledged),
+        ("timeout_packet", PacketOutcome::TimedOut),
+    ] {
+        let event = RPC
+            .shell()
+            .ibc_packet(
+                &sdk.client,
+                &IbcEventType(event_type.to_string()),
+                &packet.port_id,
+                &packet.channel_id,
+                &counterparty_port,
+                &counterparty_channel,
+                &packet.sequence,
+            )
+            .await
+            .expect("Query IBC packet event error");
+        if event.is_some() {
+            return Some(outcome);
+        }
+    }
+    Some(PacketOutcome::Unknown)
+}
+
+/// The IBC module only writes these amounts once they are non-zero, so a
+/// missing key reads as zero. Other query errors are returned.
+async fn query_amount_or_zero(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    key: &namada_core::storage::Key,
+) -> Result<token::Amount, Error> {
+    match rpc::query_storage_value(&sdk.client, key).await {
+        Err(Error::Query(QueryError::NoSuchKey(_))) => Ok(token::Amount::zero()),
+        result => result,
+    }
+}
+
+/// A token's IBC limits with the amounts already counted against them
+#[derive(Debug, Clone)]
+pub struct RateLimitUsage {
+    pub mint_limit: token::Amount,
+    /// Amount of the token currently minted over IBC
+    pub minted: token::Amount,
+    pub throughput_limit: token::Amount,
+    /// Net amount deposited or withdrawn over IBC in the current epoch
+    pub throughput: token::Amount,
+}
+
+impl RateLimitUsage {
+    /// Zero once the limit is reached
+    pub fn mint_headroom(&self) -> token::Amount {
+        self.mint_limit
+            .checked_sub(self.minted)
+            .unwrap_or_default()
+    }
+
+    pub fn throughput_headroom(&self) -> token::Amount {
+        self.throughput_limit
+            .checked_sub(self.throughput)
+            .unwrap_or_default()
+    }
+
+    /// Whether the usage of either limit is at least `warn_percent` of it
+    pub fn is_near_cap(&self, warn_percent: u64) -> bool {
+        let near = |used: token::Amount, limit: token::Amount| {
+            match (used.checked_mul(100u64), limit.checked_mul(warn_percent)) {
+                (Some(used), Some(limit)) => used >= limit,
+                // Whichever side overflows is the larger one
+                (None, _) => true,
+                (Some(_), None) => false,
+            }
+        };
+        near(self.minted, self.mint_limit) || near(self.throughput, self.throughput_limit)
+    }
+}
+
+/// Query the IBC mint and per-epoch throughput limits of the token (the
+/// defaults from the IBC parameters if it has none of its own) and their
+/// current usage
+pub async fn query_rate_limit_usage(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    token: &Address,
+) -> Result<RateLimitUsage, Error> {
+    let limits = rpc::query_ibc_rate_limits(&sdk.client, token).await?;
+    let minted = query_amount_or_zero(sdk, &storage::mint_amount_key(token)).await?;
+    let deposit = query_amount_or_zero(sdk, &storage::deposit_key(token)).await?;
+    let withdraw = query_amount_or_zero(sdk, &storage::withdraw_key(token)).await?;
+
+    Ok(RateLimitUsage {
+        mint_limit: limits.mint_limit,
+        minted,
+        throughput_limit: limits.throughput_per_epoch_limit,
+        // Same as the IBC VP, the throughput is the net flow in either direction
+        throughput: if deposit < withdraw {
+            withdraw.checked_sub(deposit).unwrap()
+        } else {
+            deposit.checked_sub(withdraw).unwrap()
+        },
+    })
+}
+
+/// Format as days, hours and minutes, e.g. `3d 4h 12m`
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+    format!("{}d {}h {}m", minutes / 1440, minutes / 60 % 24, minutes % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_expiry() {
+        let client = ClientInfo {
+            client_id: ClientId::new("07-tendermint", 0).unwrap(),
+            chain_id: ChainId::new("osmosis-1").unwrap(),
+            latest_height: Height::new(1, 100).unwrap(),
+            trusting_period: Duration::from_secs(10 * 86400),
+            frozen: false,
+            latest_timestamp: Time::from_unix_timestamp(1_700_000_000, 0).unwrap(),
+        };
+        let now = Time::from_unix_timestamp(1_700_000_000 + 86400 + 3600 + 60, 0).unwrap();
+        let left = client.time_left(now).unwrap();
+        assert_eq!(format_duration(left), "8d 22h 59m");
+
+        let later = Time::from_unix_timestamp(1_700_000_000 + 11 * 86400, 0).unwrap();
+        assert!(client.time_left(later).is_none());
+    }
+
+    #[test]
+    fn test_rate_limit_near_cap() {
+        let usage = RateLimitUsage {
+            mint_limit: token::Amount::from(1_000u64),
+            minted: token::Amount::from(790u64),
+            throughput_limit: token::Amount::from(100u64),
+            throughput: token::Amount::from(10u64),
+        };
+        assert!(!usage.is_near_cap(80));
+        assert!(usage.is_near_cap(75));
+        assert_eq!(usage.mint_headroom(), token::Amount::from(210u64));
+
+        // A zero limit is always at its cap
+        let usage = RateLimitUsage {
+            mint_limit: token::Amount::zero(),
+            ..usage
+        };
+        assert!(usage.is_near_cap(80));
+        assert_eq!(usage.mint_headroom(), token::Amount::zero());
+
+        // Limits close to the maximum amount don't overflow
+        let usage = RateLimitUsage {
+            mint_limit: token::Amount::max(),
+            throughput_limit: token::Amount::max(),
+            ..usage
+        };
+        assert!(!usage.is_near_cap(80));
+        let usage = RateLimitUsage {
+            minted: token::Amount::max(),
+            ..usage
+        };
+        assert!(usage.is_near_cap(80));
+    }
+}
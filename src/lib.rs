@@ -1,30 +1,57 @@
+pub mod address_book;
+pub mod cache;
+pub mod epoch;
+pub mod fees;
+pub mod genesis;
+pub mod gov;
+pub mod hooks;
+pub mod ibc;
+pub mod loaders;
+pub mod metrics;
+pub mod mock;
+pub mod multisig;
+pub mod pgf;
+pub mod registry;
+pub mod shielded;
+pub mod store;
+pub mod supply;
+
 use namada_core::token;
 use namada_ibc::trace::ibc_token;
 use namada_proof_of_stake::types::{BondId, BondsAndUnbondsDetail};
 use namada_sdk::address::Address;
+use namada_sdk::bip39::{Language, Mnemonic};
 use namada_sdk::collections::HashMap;
-use namada_sdk::key::common::SecretKey;
+use namada_sdk::key::common::{self, SecretKey};
+use namada_sdk::key::{RefTo, SchemeType};
 use namada_sdk::queries::vp::pos::Enriched;
-use namada_sdk::wallet::Wallet;
+use namada_sdk::wallet::{DerivationPath, Wallet};
 use namada_sdk::{
     args::TxBuilder,
     chain::ChainId,
     io::NullIo,
     masp::{fs::FsShieldedUtils, ShieldedContext},
+    rpc,
     wallet::fs::FsWalletUtils,
     Namada, NamadaImpl,
 };
+use address_book::AddressBook;
+use cache::QueryCache;
+use mock::{Fixtures, MockRpc};
 use serde::de::DeserializeOwned;
 use serde::Deserialize;
-use serde_json::from_reader;
 use std::error::Error;
 use std::fmt::Debug;
-use std::io::BufReader;
+use std::path::PathBuf;
 use std::str::FromStr;
+use std::time::Duration;
 use namada_sdk::tendermint_rpc::{HttpClient, Url};
+use namada_sdk::zeroize::Zeroizing;
 
 pub const RPC_ENV_VAR: &str = "RPC_NAMADA_UTILS";
 pub const NAMADA_UTILS_DIR: &str = "NAMADA_UTILS_DIR";
+pub const WALLET_PASSWORD_ENV_VAR: &str = "NAMADA_UTILS_WALLET_PASSWORD";
+pub const DEFAULT_DERIVATION_PATH: &str = "m/44'/877'/0'/0'/0'";
 
 // Genesis balances
 pub fn get_backer_balance() -> token::Amount {
@@ -59,22 +86,97 @@ fn get_full_path(rel_path: &str) -> String {
 #[derive(Debug, Deserialize)]
 pub struct ConfigParams {
     pub chain_id: String,
-    pub ibc_tokens: Vec<String>,
+    /// Token registry, see [`registry::TokenRegistry`]
+    #[serde(default)]
+    pub tokens: Vec<registry::TokenConfig>,
     pub transparent_addresses: Vec<String>,
+    /// The denom traces of the old `ibc_tokens` list, read into `tokens`
+    #[serde(default)]
+    ibc_tokens: Vec<String>,
+    /// Viewing keys (raw `zvknam` keys or wallet aliases) for shielded reports
+    #[serde(default)]
+    pub viewing_keys: Vec<String>,
+    /// Loaded from [`address_book::ADDRESS_BOOK_FILE`] next to the config
+    #[serde(skip)]
+    pub address_book: AddressBook,
 }
 
 fn load_config(rel_path: &str) -> Result<ConfigParams, Box<dyn std::error::Error>> {
     let path = get_full_path(rel_path);
     let contents = std::fs::read_to_string(path.as_str())?;
-    let config: ConfigParams = toml::from_str(&contents)?;
+    let mut config: ConfigParams = toml::from_str(&contents)?;
+    if !config.ibc_tokens.is_empty() {
+        eprintln!("WARNING: `ibc_tokens` in {} is deprecated, use [[tokens]] entries", path);
+    }
+    for trace in std::mem::take(&mut config.ibc_tokens) {
+        if !config.tokens.iter().any(|token| token.trace.as_ref() == Some(&trace)) {
+            config.tokens.push(registry::TokenConfig::from_legacy_trace(&trace));
+        }
+    }
+    registry::check_token_configs(&config.tokens)?;
     Ok(config)
 }
 
-pub async fn build_ctx() -> (
+/// Where the RPC queries go: the node at `RPC_NAMADA_UTILS`, optionally
+/// recorded, or a recording replayed without network access
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct RpcArgs {
+    /// Record every RPC query and response into this directory
+    #[arg(long, global = true, conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    /// Answer the RPC queries from a recording instead of the node
+    #[arg(long, global = true)]
+    pub replay: Option<PathBuf>,
+}
+
+/// Query cache of the read-only reports. The tools that build txs don't take
+/// it, so they never read balances or revealed keys from a stale cache.
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct CacheArgs {
+    /// Cache the query results in this SQLite file across runs
+    #[arg(long, global = true, conflicts_with = "replay")]
+    pub cache: Option<PathBuf>,
+
+    /// Seconds before a cached query of the latest block is asked again.
+    /// Queries pinned to a height never expire.
+    #[arg(long = "cache-ttl", global = true, default_value_t = 60)]
+    pub cache_ttl: u64,
+}
+
+pub async fn build_ctx(
+    rpc: &RpcArgs,
+) -> (
     NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
     ConfigParams,
 ) {
-    let rpc_url = std::env::var(RPC_ENV_VAR).expect("RPC_NAMADA_UTILS env var not set");
+    build_report_ctx(rpc, &CacheArgs::default()).await
+}
+
+/// [`build_ctx`] for the read-only reports, with the queries going through
+/// the `--cache`
+pub async fn build_report_ctx(
+    rpc: &RpcArgs,
+    cache: &CacheArgs,
+) -> (
+    NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    ConfigParams,
+) {
+    let rpc_url = match &rpc.replay {
+        Some(dir) => MockRpc::start(Fixtures::load(dir)).await.url,
+        None => {
+            let mut url = std::env::var(RPC_ENV_VAR).expect("RPC_NAMADA_UTILS env var not set");
+            if let Some(path) = &cache.cache {
+                let cache = QueryCache::open(path, Duration::from_secs(cache.cache_ttl));
+                url = MockRpc::cache(&url, cache).await.url;
+            }
+            // Recorded after the cache, so cached answers are recorded too
+            if let Some(dir) = &rpc.record {
+                url = MockRpc::record(&url, dir).await.url;
+            }
+            url
+        }
+    };
     let url = Url::from_str(&rpc_url).expect("Invalid RPC address");
     let http_client = HttpClient::new(url).unwrap();
 
@@ -84,7 +186,8 @@ pub async fn build_ctx() -> (
     let shielded_ctx = ShieldedContext::new(FsShieldedUtils::new("./masp".into()));
     let null_io = NullIo;
 
-    let config = load_config("config/config.toml").expect("Could not load config file");
+    let mut config = load_config("config/config.toml").expect("Could not load config file");
+    config.address_book = AddressBook::load().expect("Could not parse the address book");
 
     let sdk = NamadaImpl::new(http_client, wallet, shielded_ctx.into(), null_io)
         .await
@@ -94,42 +197,121 @@ pub async fn build_ctx() -> (
     (sdk, config)
 }
 
+/// An address from a `tnam` string, an address book label or a wallet alias
+pub fn resolve_address(
+    wallet: &Wallet<FsWalletUtils>,
+    book: &AddressBook,
+    addr: &str,
+) -> Address {
+    if addr.starts_with("tnam") {
+        Address::from_str(addr).expect("Could not parse address")
+    } else if let Some(address) = book.find(addr) {
+        address.clone()
+    } else {
+        wallet
+            .find_address(addr)
+            .expect("Could not find address in wallet")
+            .into_owned()
+    }
+}
+
 pub fn get_addresses(wallet: &Wallet<FsWalletUtils>, config: &ConfigParams) -> Vec<Address> {
     config
         .transparent_addresses
         .iter()
-        .map(|addr| if addr.starts_with("tnam") {Address::from_str(addr).expect("Could not parse address")} else {
-            wallet.find_address(addr).expect("Could not find address in wallet").into_owned()
-        })
+        .map(|addr| resolve_address(wallet, &config.address_book, addr))
         .collect()
 }
 
-pub fn get_addresses_from_file(rel_path: &str) -> Vec<Address> {
+/// An address as the reports print it: `Label (tnam1...)` with its address
+/// book label, else with the alias it is configured by or has in the wallet,
+/// else the bare address
+pub fn display_address(
+    wallet: &Wallet<FsWalletUtils>,
+    config: &ConfigParams,
+    address: &Address,
+) -> String {
+    if config.address_book.label(address).is_some() {
+        return config.address_book.display(address);
+    }
+    let configured = config.transparent_addresses.iter().find(|entry| {
+        !entry.starts_with("tnam") && wallet.find_address(entry).as_deref() == Some(address)
+    });
+    let alias = configured
+        .cloned()
+        .or_else(|| wallet.find_alias(address).map(|alias| alias.to_string()));
+    match alias {
+        Some(alias) => format!("{} ({})", alias, address),
+        None => address.to_string(),
+    }
+}
+
+/// The configured addresses, or the address book entries with `tag`
+pub fn get_addresses_or_tagged(
+    wallet: &Wallet<FsWalletUtils>,
+    config: &ConfigParams,
+    tag: Option<&str>,
+) -> Vec<Address> {
+    match tag {
+        Some(tag) => {
+            let addresses = config.address_book.tagged(tag);
+            assert!(!addresses.is_empty(), "No address tagged {} in the address book", tag);
+            addresses
+        }
+        None => get_addresses(wallet, config),
+    }
+}
+
+/// The addresses of a file, one per line with `#` comments, and the address
+/// book entries in the group named after the file, e.g. `backers` for
+/// `config/backers.txt`. Repeated addresses are reported and skipped.
+pub fn get_addresses_from_file(book: &AddressBook, rel_path: &str) -> Vec<Address> {
     let path = get_full_path(rel_path);
-    let addresses = std::fs::read_to_string(path).expect("Could not read addresses file");
+    let contents = std::fs::read_to_string(&path).expect("Could not read addresses file");
+    let list = loaders::parse_address_list(&contents)
+        .unwrap_or_else(|e| panic!("Could not parse {}, {}", path, e));
+    for (line, first_line, address) in &list.duplicates {
+        eprintln!(
+            "WARNING: {} on line {} of {} is already on line {}, skipped",
+            address, line, path, first_line
+        );
+    }
+    let mut addresses = list.addresses;
+    let group = std::path::Path::new(rel_path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default();
+    for address in book.group(group) {
+        if !addresses.contains(&address) {
+            addresses.push(address);
+        }
+    }
     addresses
-        .lines()
-        .map(|line| Address::from_str(line).expect("Could not parse address"))
-        .collect()
 }
 
 pub fn get_address_from_ibc_denom(denom: &str) -> Address {
     ibc_token(denom)
 }
 
-#[derive(Deserialize, Debug)]
-pub struct Record {
-    pub address: String,
-    pub amount: u64,
-    pub category: String,
-    pub name: String,
-}
+pub use loaders::Record;
 
-pub fn get_genesis_accounts(rel_path: &str) -> Vec<Record> {
+/// Genesis accounts from a JSON, TOML or CSV file, see
+/// [`loaders::parse_records`]. Records without a name are named after their
+/// address book label.
+pub fn get_genesis_accounts(book: &AddressBook, rel_path: &str) -> Vec<Record> {
     let path = get_full_path(rel_path);
-    let file = std::fs::File::open(path).expect("Could not open genesis accounts file");
-    let reader = BufReader::new(file);
-    from_reader(reader).expect("Could not parse genesis accounts file")
+    let contents = std::fs::read_to_string(&path).expect("Could not read genesis accounts file");
+    let mut records = loaders::parse_records(std::path::Path::new(&path), &contents)
+        .unwrap_or_else(|e| panic!("Could not parse {}, {}", path, e));
+    for record in records.iter_mut().filter(|record| record.name.is_empty()) {
+        if let Some(label) = book.label(&record.address) {
+            record.name = label.to_string();
+        }
+    }
+    for address in loaders::duplicate_records(&records) {
+        eprintln!("WARNING: {} has more than one record in {}", address, path);
+    }
+    records
 }
 
 pub fn get_top_validators() -> HashMap<String, Address> {
@@ -182,6 +364,107 @@ pub fn get_bonds_to_top_validators(
     bonds_to_top_validators
 }
 
+/// A genesis allocation category whose stake is traced through its accounts
+pub struct GenesisCategory {
+    pub name: &'static str,
+    pub genesis_balance: token::Amount,
+    /// Address list or genesis accounts file, relative to `NAMADA_UTILS_DIR`
+    pub file: &'static str,
+}
+
+impl GenesisCategory {
+    /// The addresses whose bonds count towards the category
+    pub fn delegators(&self, book: &AddressBook) -> Vec<Address> {
+        if self.file.ends_with(".txt") {
+            return get_addresses_from_file(book, self.file);
+        }
+        get_genesis_accounts(book, self.file)
+            .into_iter()
+            .map(|record| record.address)
+            .collect()
+    }
+}
+
+/// The traced genesis categories. The rest of the stake is assumed to come
+/// from the public allocations, see [`get_public_alloc_stake`].
+pub fn get_genesis_categories() -> [GenesisCategory; 4] {
+    [
+        GenesisCategory {
+            name: "Backers",
+            genesis_balance: get_backer_balance(),
+            file: "config/backers.txt",
+        },
+        GenesisCategory {
+            name: "Core team",
+            genesis_balance: get_core_balance(),
+            file: "config/core_team.txt",
+        },
+        GenesisCategory {
+            name: "R&D ecosystems",
+            genesis_balance: get_rd_balance(),
+            file: "config/rd_ecosystem_dev.json",
+        },
+        GenesisCategory {
+            name: "Future allocations",
+            genesis_balance: get_future_alloc_balance(),
+            file: "config/public_allocations_future.json",
+        },
+    ]
+}
+
+/// Stake of the public allocations: whatever the traced categories don't
+/// account for
+pub fn get_public_alloc_stake(
+    total_staked: token::Amount,
+    category_stakes: impl IntoIterator<Item = token::Amount>,
+) -> token::Amount {
+    let known = category_stakes
+        .into_iter()
+        .fold(token::Amount::zero(), |acc, staked| acc.checked_add(staked).unwrap());
+    total_staked.checked_sub(known).unwrap_or_default()
+}
+
+/// A consensus validator with its metadata name, or else its address book
+/// label
+pub struct TopValidator {
+    pub address: Address,
+    pub name: Option<String>,
+    pub stake: token::Amount,
+}
+
+/// The `num_vals` consensus validators with the most stake at `epoch`, largest
+/// first
+pub async fn query_top_validators(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    book: &AddressBook,
+    epoch: namada_core::chain::Epoch,
+    num_vals: usize,
+) -> Result<Vec<TopValidator>, String> {
+    let mut consensus_validators = rpc::get_all_consensus_validators(&sdk.client, epoch)
+        .await
+        .map_err(|e| format!("Query consensus validators error: {}", e))?
+        .into_iter()
+        .collect::<Vec<_>>();
+    consensus_validators.sort_by(|a, b| b.bonded_stake.cmp(&a.bonded_stake));
+
+    let mut validators = Vec::new();
+    for val in consensus_validators.into_iter().take(num_vals) {
+        let metadata = rpc::query_metadata(&sdk.client, &val.address, Some(epoch))
+            .await
+            .map_err(|e| format!("Query validator metadata error: {}", e))?
+            .0;
+        let name = metadata
+            .and_then(|metadata| metadata.name)
+            .or_else(|| book.label(&val.address).map(str::to_string));
+        validators.push(TopValidator {
+            address: val.address,
+            name,
+            stake: val.bonded_stake,
+        });
+    }
+    Ok(validators)
+}
+
 // Function to read a CSV file and parse it into an object that can be implemented later
 pub fn read_csv_to_vec<T>(rel_path: &str) -> Result<Vec<T>, Box<dyn Error>>
 where
@@ -206,24 +489,116 @@ struct AddressWithKey {
     pk: String,
 }
 
+/// Read the wallet encryption password from `NAMADA_UTILS_WALLET_PASSWORD`,
+/// or prompt for it on the terminal without echoing.
+pub fn read_wallet_password(confirm: bool) -> Zeroizing<String> {
+    if let Ok(password) = std::env::var(WALLET_PASSWORD_ENV_VAR) {
+        if !password.is_empty() {
+            return Zeroizing::new(password);
+        }
+    }
+    loop {
+        let password = Zeroizing::new(
+            rpassword::prompt_password("Wallet encryption password: ")
+                .expect("Failed to read password"),
+        );
+        if password.is_empty() {
+            println!("The password cannot be empty.");
+            continue;
+        }
+        if confirm {
+            let confirmation = Zeroizing::new(
+                rpassword::prompt_password("Confirm password: ")
+                    .expect("Failed to read password"),
+            );
+            if *password != *confirmation {
+                println!("Passwords did not match, try again.");
+                continue;
+            }
+        }
+        return password;
+    }
+}
+
+/// Import the raw secret keys in the given CSV into the wallet as `key-{idx}`,
+/// encrypted with `password`. Secret material is never printed.
 pub async fn load_keys(
     sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
     rel_path: &str,
+    password: Zeroizing<String>,
 ) {
     let data = read_csv_to_vec::<AddressWithKey>(rel_path).expect("Failed to read CSV");
-    for (idx, AddressWithKey { address, pk }) in data.iter().enumerate() {
-        println!("{}: {}", address, pk);
-        let sk = SecretKey::from_str(pk).expect("Failed to parse secret key");
-        let addr = Address::from_str(address).expect("Failed to parse address");
-
-        sdk.wallet_mut()
-            .await
-            .insert_keypair(format!("key-{}", idx), false, sk, None, Some(addr), None)
-            .expect("Failed to store keypair in wallet");
+    for (idx, AddressWithKey { address, pk }) in data.into_iter().enumerate() {
+        let pk = Zeroizing::new(pk);
+        let sk = SecretKey::from_str(&pk).expect("Failed to parse secret key");
+        let addr = Address::from_str(&address).expect("Failed to parse address");
+        let alias = format!("key-{}", idx);
+
+        match sdk.wallet_mut().await.insert_keypair(
+            alias.clone(),
+            false,
+            sk,
+            Some(password.clone()),
+            Some(addr),
+            None,
+        ) {
+            Some(alias) => println!("Imported {}: {}", alias, address),
+            None => println!("Skipped {}: {} (already in wallet)", alias, address),
+        }
     }
     sdk.wallet().await.save().expect("Could not save wallet!");
 }
 
+/// Derive a key from a BIP39 mnemonic (prompted for without echoing) at the
+/// given derivation path and store it encrypted under `alias`.
+pub async fn load_mnemonic_key(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    alias: &str,
+    derivation_path: &str,
+    prompt_bip39_passphrase: bool,
+    password: Zeroizing<String>,
+) -> common::PublicKey {
+    let derivation_path = DerivationPath::from_path_string_for_transparent_scheme(
+        SchemeType::Ed25519,
+        derivation_path,
+    )
+    .expect("Invalid derivation path");
+
+    let phrase = Zeroizing::new(
+        rpassword::prompt_password("BIP39 mnemonic: ").expect("Failed to read mnemonic"),
+    );
+    let mnemonic = Mnemonic::from_phrase(phrase.trim(), Language::English)
+        .expect("Invalid BIP39 mnemonic");
+    let passphrase = if prompt_bip39_passphrase {
+        Zeroizing::new(
+            rpassword::prompt_password("BIP39 passphrase: ")
+                .expect("Failed to read passphrase"),
+        )
+    } else {
+        Zeroizing::default()
+    };
+
+    let (alias, sk) = sdk
+        .wallet_mut()
+        .await
+        .derive_store_key_from_mnemonic_code(
+            SchemeType::Ed25519,
+            Some(alias.to_string()),
+            false,
+            derivation_path,
+            Some((mnemonic, passphrase)),
+            false,
+            Some(password),
+        )
+        .expect("Failed to store derived key in wallet");
+    sdk.wallet().await.save().expect("Could not save wallet!");
+
+    let pk = sk.ref_to();
+    let address = Address::from(&pk);
+    println!("Imported {}: {}", alias, address);
+    pk
+}
+
 // Write some tests
 #[cfg(test)]
 mod tests {
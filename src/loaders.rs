@@ -0,0 +1,313 @@
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use namada_core::string_encoding::{ADDRESS_HRP, COMMON_PK_HRP, MASP_PAYMENT_ADDRESS_HRP};
+use namada_core::token;
+use namada_sdk::address::Address;
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+
+/// An error in a loaded file, at a line when known
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadError {
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl LoadError {
+    fn at(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line: Some(line),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+/// A transparent Namada address. Shielded addresses, keys and addresses of
+/// other chains are rejected with what they look like.
+pub fn parse_address(raw: &str) -> Result<Address, String> {
+    // The bech32 data part never contains a `1`
+    let hrp = raw.rsplit_once('1').map(|(hrp, _)| hrp).unwrap_or_default();
+    match hrp {
+        ADDRESS_HRP => {
+            Address::from_str(raw).map_err(|e| format!("invalid address {}: {}", raw, e))
+        }
+        MASP_PAYMENT_ADDRESS_HRP => Err(format!(
+            "{} is a shielded payment address, expected a transparent tnam address",
+            raw
+        )),
+        COMMON_PK_HRP => Err(format!("{} is a public key, expected a tnam address", raw)),
+        "" => Err(format!(
+            "{} is not an address, expected a tnam address",
+            raw
+        )),
+        other => Err(format!(
+            "{} is an address of another network ({}), expected a Namada tnam address",
+            raw, other
+        )),
+    }
+}
+
+/// A raw token amount, without decimal places
+pub fn parse_amount(raw: &str) -> Result<token::Amount, String> {
+    token::Amount::from_str(raw.trim(), 0u8).map_err(|e| format!("invalid amount {}: {}", raw, e))
+}
+
+/// Addresses of an address list, in order and without duplicates
+#[derive(Debug, Default, PartialEq)]
+pub struct AddressList {
+    pub addresses: Vec<Address>,
+    /// Line of every repeated address, with the line it first appeared on
+    pub duplicates: Vec<(usize, usize, Address)>,
+}
+
+/// One address per line. Blank lines and `#` comments are skipped.
+pub fn parse_address_list(contents: &str) -> Result<AddressList, LoadError> {
+    let mut list = AddressList::default();
+    let mut first_lines = Vec::new();
+    for (idx, line) in contents.lines().enumerate() {
+        let line_no = idx + 1;
+        let entry = line.split('#').next().unwrap_or_default().trim();
+        if entry.is_empty() {
+            continue;
+        }
+        let address = parse_address(entry).map_err(|e| LoadError::at(line_no, e))?;
+        match list.addresses.iter().position(|known| known == &address) {
+            Some(pos) => list.duplicates.push((line_no, first_lines[pos], address)),
+            None => {
+                list.addresses.push(address);
+                first_lines.push(line_no);
+            }
+        }
+    }
+    Ok(list)
+}
+
+/// A genesis account
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Record {
+    #[serde(deserialize_with = "deserialize_address")]
+    pub address: Address,
+    /// Raw amount, a number or a string for amounts beyond `u64`. Written as
+    /// a string.
+    #[serde(
+        serialize_with = "serialize_amount",
+        deserialize_with = "deserialize_amount"
+    )]
+    pub amount: token::Amount,
+    pub category: String,
+    pub name: String,
+}
+
+fn deserialize_address<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Address, D::Error> {
+    let raw = String::deserialize(deserializer)?;
+    parse_address(&raw).map_err(de::Error::custom)
+}
+
+fn serialize_amount<S: Serializer>(
+    amount: &token::Amount,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&amount.to_string())
+}
+
+fn deserialize_amount<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<token::Amount, D::Error> {
+    struct AmountVisitor;
+
+    impl Visitor<'_> for AmountVisitor {
+        type Value = token::Amount;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a raw amount as an integer or a string")
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+            Ok(token::Amount::from(value))
+        }
+
+        fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+            u64::try_from(value)
+                .map(token::Amount::from)
+                .map_err(|_| E::custom(format!("negative amount {}", value)))
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+            parse_amount(value).map_err(E::custom)
+        }
+    }
+
+    deserializer.deserialize_any(AmountVisitor)
+}
+
+#[derive(Deserialize)]
+struct TomlRecords {
+    #[serde(default)]
+    records: Vec<Record>,
+}
+
+/// CSV fields are read as text, so that amounts beyond `u64` keep all their
+/// digits
+#[derive(Deserialize)]
+struct CsvRecord {
+    address: String,
+    amount: String,
+    category: String,
+    name: String,
+}
+
+/// Line of a byte offset
+fn line_of(contents: &str, offset: usize) -> usize {
+    let before = &contents.as_bytes()[..offset.min(contents.len())];
+    before.iter().filter(|byte| **byte == b'\n').count() + 1
+}
+
+/// Genesis accounts from a JSON array, a TOML file of `[[records]]` or a CSV
+/// file with an `address,amount,category,name` header, by file extension.
+/// In CSV files, blank lines and lines starting with `#` are skipped.
+pub fn parse_records(path: &Path, contents: &str) -> Result<Vec<Record>, LoadError> {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => serde_json::from_str(contents).map_err(|e| {
+            // The line is kept apart from the message, as for the other formats
+            let message = e.to_string();
+            let message = message.split(" at line ").next().unwrap_or_default();
+            LoadError::at(e.line(), message)
+        }),
+        Some("toml") => toml::from_str::<TomlRecords>(contents)
+            .map(|file| file.records)
+            .map_err(|e| LoadError {
+                line: e.span().map(|span| line_of(contents, span.start)),
+                message: e.message().to_string(),
+            }),
+        Some("csv") => {
+            let csv_error = |e: csv::Error| LoadError {
+                line: e.position().map(|pos| pos.line() as usize),
+                message: e.to_string(),
+            };
+            let mut reader = csv::ReaderBuilder::new()
+                .comment(Some(b'#'))
+                .trim(csv::Trim::All)
+                .from_reader(contents.as_bytes());
+            let headers = reader.headers().map_err(csv_error)?.clone();
+            let mut records = Vec::new();
+            for row in reader.records() {
+                let row = row.map_err(csv_error)?;
+                let line = row.position().map_or(0, |pos| pos.line() as usize);
+                let raw: CsvRecord = row
+                    .deserialize(Some(&headers))
+                    .map_err(|e| LoadError::at(line, e.to_string()))?;
+                records.push(Record {
+                    address: parse_address(&raw.address).map_err(|e| LoadError::at(line, e))?,
+                    amount: parse_amount(&raw.amount).map_err(|e| LoadError::at(line, e))?,
+                    category: raw.category,
+                    name: raw.name,
+                });
+            }
+            Ok(records)
+        }
+        _ => Err(LoadError {
+            line: None,
+            message: String::from("expected a .json, .toml or .csv file"),
+        }),
+    }
+}
+
+/// Addresses that appear in more than one record
+pub fn duplicate_records(records: &[Record]) -> Vec<&Address> {
+    let mut duplicates = Vec::new();
+    for (idx, record) in records.iter().enumerate() {
+        if records[..idx]
+            .iter()
+            .any(|other| other.address == record.address)
+            && !duplicates.contains(&&record.address)
+        {
+            duplicates.push(&record.address);
+        }
+    }
+    duplicates
+}
+
+#[cfg(test)]
+mod tests {
+    use namada_core::address::gen_deterministic_established_address;
+
+    use super::*;
+
+    #[test]
+    fn test_loaders() {
+        let first = gen_deterministic_established_address("first");
+        let second = gen_deterministic_established_address("second");
+
+        let list = parse_address_list(&format!(
+            "# Backers\n{}  \n\n{} # fund\n{}\n",
+            first, second, first
+        ))
+        .unwrap();
+        assert_eq!(list.addresses, vec![first.clone(), second.clone()]);
+        assert_eq!(list.duplicates, vec![(5, 2, first.clone())]);
+
+        let err = parse_address_list(&format!(
+            "{}\nosmo1qyqszqgpqyqszqgpqyqszqgpqyqszqgpjnp7du\n",
+            first
+        ))
+        .unwrap_err();
+        assert_eq!(err.line, Some(2));
+        assert!(err.message.contains("another network (osmo)"));
+
+        // Amounts beyond u64 as strings, in every format
+        let big = "100000000000000000000000";
+        let json = format!(
+            r#"[
+                {{"address": "{}", "amount": 5, "category": "backers", "name": "A"}},
+                {{"address": "{}", "amount": "{}", "category": "backers", "name": "B"}}
+            ]"#,
+            first, second, big
+        );
+        let toml = format!(
+            "[[records]]\naddress = \"{}\"\namount = 5\ncategory = \"backers\"\nname = \"A\"\n\n\
+             [[records]]\naddress = \"{}\"\namount = \"{}\"\ncategory = \"backers\"\nname = \"B\"\n",
+            first, second, big
+        );
+        let csv = format!(
+            "address,amount,category,name\n# Backers\n{},5,backers,A\n\n{},{},backers,B\n",
+            first, second, big
+        );
+        for (file, contents) in [("a.json", json), ("a.toml", toml), ("a.csv", csv)] {
+            let records = parse_records(Path::new(file), &contents).unwrap();
+            assert_eq!(records.len(), 2, "{}", file);
+            assert_eq!(records[0].amount, token::Amount::from(5u64));
+            assert_eq!(records[1].amount, parse_amount(big).unwrap());
+            assert_eq!(records[1].address, second);
+        }
+
+        let err = parse_records(
+            Path::new("a.csv"),
+            &format!(
+                "address,amount,category,name\n{},5,backers,A\n{},1.5,backers,B\n",
+                first, second
+            ),
+        )
+        .unwrap_err();
+        assert_eq!(err.line, Some(3));
+        let err = parse_records(
+            Path::new("a.json"),
+            "[\n{\"address\": \"znam1abc\", \"amount\": 1, \"category\": \"\", \"name\": \"\"}\n]",
+        )
+        .unwrap_err();
+        assert_eq!(err.line, Some(2));
+        assert!(err.message.contains("shielded payment address"));
+    }
+}
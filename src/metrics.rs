@@ -0,0 +1,275 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use namada_sdk::io::NullIo;
+use namada_sdk::masp::fs::FsShieldedUtils;
+use namada_sdk::tendermint_rpc::HttpClient;
+use namada_sdk::wallet::fs::FsWalletUtils;
+use namada_sdk::{rpc, time::DateTimeUtc, Namada, NamadaImpl};
+use namada_token::Dec;
+
+use crate::registry::TokenRegistry;
+
+/// Name and help text of every gauge, in the order they are rendered
+const GAUGES: [(&str, &str); 8] = [
+    ("namada_block_height", "Height of the last committed block"),
+    ("namada_epoch", "Current epoch"),
+    (
+        "namada_staked_ratio",
+        "Share of the effective native supply that is bonded",
+    ),
+    ("namada_pgf_balance", "NAM balance of the PGF account"),
+    (
+        "namada_governance_balance",
+        "NAM balance of the governance account",
+    ),
+    (
+        "namada_token_total_supply",
+        "Total supply of the token, effective supply for NAM",
+    ),
+    (
+        "namada_token_masp_balance",
+        "Balance of the token in the MASP",
+    ),
+    (
+        "namada_last_refresh_timestamp_seconds",
+        "Unix time of the last refresh",
+    ),
+];
+
+const RPC_ERRORS: &str = "namada_rpc_errors_total";
+
+/// Query names of the error counter, each exported from 0 so a series never
+/// appears out of nowhere
+const QUERIES: [&str; 8] = [
+    "block",
+    "epoch",
+    "native_supply",
+    "total_staked",
+    "pgf_balance",
+    "governance_balance",
+    "total_supply",
+    "masp_balance",
+];
+
+/// Chain and token metrics in the Prometheus text format. Gauges are replaced
+/// on every refresh and a gauge whose query failed is left out; the error
+/// counter keeps counting across refreshes.
+#[derive(Debug, Clone)]
+pub struct Metrics {
+    chain_id: String,
+    /// Gauge name, token symbol and value
+    samples: Vec<(&'static str, Option<String>, String)>,
+    /// Failed queries by name
+    rpc_errors: BTreeMap<&'static str, u64>,
+}
+
+impl Metrics {
+    pub fn new(chain_id: &str) -> Self {
+        Self {
+            chain_id: chain_id.to_string(),
+            samples: Vec::new(),
+            rpc_errors: QUERIES.iter().map(|query| (*query, 0)).collect(),
+        }
+    }
+
+    pub fn set(&mut self, name: &'static str, token: Option<&str>, value: impl Display) {
+        self.samples
+            .push((name, token.map(str::to_string), value.to_string()));
+    }
+
+    /// The query's value, or `None` after counting and printing its error
+    pub fn record<T, E: Display>(
+        &mut self,
+        query: &'static str,
+        result: Result<T, E>,
+    ) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(e) => {
+                *self.rpc_errors.entry(query).or_default() += 1;
+                println!("Query {} error: {}", query, e);
+                None
+            }
+        }
+    }
+
+    /// Re-query every gauge
+    pub async fn refresh(
+        &mut self,
+        sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+        registry: &TokenRegistry,
+    ) {
+        self.samples.clear();
+        let native_token = sdk.native_token();
+
+        let last_block = rpc::query_block(&sdk.client).await;
+        if let Some(Some(last_block)) = self.record("block", last_block) {
+            self.set("namada_block_height", None, last_block.height.0);
+        }
+
+        let epoch = rpc::query_epoch(&sdk.client).await;
+        let epoch = self.record("epoch", epoch);
+        let native_supply = rpc::get_effective_native_supply(&sdk.client).await;
+        let native_supply = self.record("native_supply", native_supply);
+        if let Some(epoch) = epoch {
+            self.set("namada_epoch", None, epoch);
+            let total_staked = rpc::get_total_staked_tokens(&sdk.client, epoch).await;
+            if let (Some(total_staked), Some(native_supply)) =
+                (self.record("total_staked", total_staked), native_supply)
+            {
+                let staked_ratio = Dec::try_from(total_staked)
+                    .unwrap()
+                    .checked_div(Dec::try_from(native_supply).unwrap())
+                    .unwrap_or_default();
+                self.set("namada_staked_ratio", None, staked_ratio);
+            }
+        }
+
+        for (name, alias, query) in [
+            ("namada_pgf_balance", "pgf", "pgf_balance"),
+            (
+                "namada_governance_balance",
+                "governance",
+                "governance_balance",
+            ),
+        ] {
+            let address = sdk.wallet().await.find_address(alias).unwrap().into_owned();
+            let balance = rpc::get_token_balance(&sdk.client, &native_token, &address, None).await;
+            if let Some(balance) = self.record(query, balance) {
+                self.set(name, None, balance.to_string_native());
+            }
+        }
+
+        if let Some(native_supply) = native_supply {
+            self.set(
+                "namada_token_total_supply",
+                Some("NAM"),
+                native_supply.to_string_native(),
+            );
+        }
+        let masp_address = sdk
+            .wallet()
+            .await
+            .find_address("masp")
+            .unwrap()
+            .into_owned();
+        for token in registry.ibc_tokens() {
+            let total_supply = rpc::get_token_total_supply(&sdk.client, &token.address).await;
+            if let Some(total_supply) = self.record("total_supply", total_supply) {
+                self.set(
+                    "namada_token_total_supply",
+                    Some(&token.symbol),
+                    token.format_amount(total_supply),
+                );
+            }
+            let masp_balance =
+                rpc::get_token_balance(&sdk.client, &token.address, &masp_address, None).await;
+            if let Some(masp_balance) = self.record("masp_balance", masp_balance) {
+                self.set(
+                    "namada_token_masp_balance",
+                    Some(&token.symbol),
+                    token.format_amount(masp_balance),
+                );
+            }
+        }
+
+        let now = DateTimeUtc::now().to_unix_timestamp();
+        self.set("namada_last_refresh_timestamp_seconds", None, now);
+    }
+
+    fn labels(&self, token: Option<&str>, query: Option<&str>) -> String {
+        let mut labels = format!("chain_id=\"{}\"", escape(&self.chain_id));
+        if let Some(token) = token {
+            labels.push_str(&format!(",token=\"{}\"", escape(token)));
+        }
+        if let Some(query) = query {
+            labels.push_str(&format!(",query=\"{}\"", escape(query)));
+        }
+        labels
+    }
+
+    /// Render in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for (name, help) in GAUGES {
+            let samples: Vec<_> = self
+                .samples
+                .iter()
+                .filter(|(sample, _, _)| *sample == name)
+                .collect();
+            if samples.is_empty() {
+                continue;
+            }
+            out.push_str(&format!(
+                "# HELP {} {}\n# TYPE {} gauge\n",
+                name, help, name
+            ));
+            for (_, token, value) in samples {
+                out.push_str(&format!(
+                    "{}{{{}}} {}\n",
+                    name,
+                    self.labels(token.as_deref(), None),
+                    value
+                ));
+            }
+        }
+
+        out.push_str(&format!(
+            "# HELP {} Failed RPC queries since the exporter started\n# TYPE {} counter\n",
+            RPC_ERRORS, RPC_ERRORS
+        ));
+        for (query, count) in &self.rpc_errors {
+            out.push_str(&format!(
+                "{}{{{}}} {}\n",
+                RPC_ERRORS,
+                self.labels(None, Some(query)),
+                count
+            ));
+        }
+        out
+    }
+}
+
+/// Escape a label value
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_metrics() {
+        let mut metrics = Metrics::new("namada.5f5de2dd1b88cba30586420");
+        metrics.set("namada_token_masp_balance", Some("OSMO"), "12.5");
+        metrics.set("namada_epoch", None, 42);
+        metrics.record::<(), _>("epoch", Err("timeout"));
+        metrics.record::<(), _>("epoch", Err("timeout"));
+
+        let text = metrics.render();
+        let chain = "chain_id=\"namada.5f5de2dd1b88cba30586420\"";
+        assert!(text.contains("# TYPE namada_epoch gauge\n"));
+        assert!(text.contains(&format!("namada_epoch{{{}}} 42\n", chain)));
+        assert!(text.contains(&format!(
+            "namada_token_masp_balance{{{},token=\"OSMO\"}} 12.5\n",
+            chain
+        )));
+        assert!(text.contains(&format!(
+            "namada_rpc_errors_total{{{},query=\"epoch\"}} 2\n",
+            chain
+        )));
+        assert!(text.contains(&format!(
+            "namada_rpc_errors_total{{{},query=\"block\"}} 0\n",
+            chain
+        )));
+        // Gauges are rendered in a fixed order and missing ones are left out
+        assert!(text.find("namada_epoch").unwrap() < text.find("namada_token_masp").unwrap());
+        assert!(!text.contains("namada_block_height"));
+        assert_eq!(escape("a\"b"), "a\\\"b");
+    }
+}
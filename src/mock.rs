@@ -0,0 +1,591 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+
+#[cfg(any(test, feature = "fixtures"))]
+use base64::prelude::{Engine, BASE64_STANDARD};
+#[cfg(any(test, feature = "fixtures"))]
+use namada_core::borsh::{BorshSerialize, BorshSerializeExt};
+#[cfg(any(test, feature = "fixtures"))]
+use namada_core::storage;
+#[cfg(any(test, feature = "fixtures"))]
+use namada_sdk::queries::RPC;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::cache::{CacheKey, QueryCache};
+
+/// File the fixtures are saved to in a fixtures directory
+pub const FIXTURES_FILE: &str = "fixtures.json";
+
+/// Prefix of raw storage reads, which a node answers with an empty value for
+/// missing keys
+const STORAGE_VALUE_PREFIX: &str = "/shell/value/";
+
+/// An `abci_query` answer: the base64 response data of a query, or the code
+/// and info of the error the node answered with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct QueryFixture {
+    #[serde(flatten)]
+    key: CacheKey,
+    value: String,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    code: u64,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    info: String,
+}
+
+fn is_zero(code: &u64) -> bool {
+    *code == 0
+}
+
+/// The `result` of any other JSON-RPC call, e.g. `block` or `status`, or the
+/// `error` the node answered with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CallFixture {
+    method: String,
+    params: Value,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    result: Value,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    error: Value,
+}
+
+/// Borsh-encoded ABCI query responses by query path, data and height, and
+/// the results of other JSON-RPC calls by method and params. The paths are
+/// best built with the SDK's router, e.g.
+/// `RPC.vp().pos().total_stake_path(&Some(epoch))`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Fixtures {
+    #[serde(default)]
+    queries: Vec<QueryFixture>,
+    #[serde(default)]
+    calls: Vec<CallFixture>,
+    /// Answer the storage reads without a fixture with an empty value, as a
+    /// node does for missing keys, instead of failing
+    #[serde(skip)]
+    empty_storage: bool,
+}
+
+/// Builders for the fixtures of tests, with the `fixtures` feature
+#[cfg(any(test, feature = "fixtures"))]
+impl Fixtures {
+    /// Response of the query of `path` at the latest block without data
+    pub fn insert(&mut self, path: impl Into<String>, value: &impl BorshSerialize) {
+        self.insert_raw(path, value.serialize_to_vec());
+    }
+
+    pub fn insert_raw(&mut self, path: impl Into<String>, data: Vec<u8>) {
+        let key = CacheKey {
+            path: path.into(),
+            data: String::new(),
+            height: 0,
+        };
+        self.insert_query(QueryFixture {
+            key,
+            value: BASE64_STANDARD.encode(data),
+            code: 0,
+            info: String::new(),
+        });
+    }
+
+    /// Value of a storage key, as read by `rpc::query_storage_value` and the
+    /// balance queries
+    pub fn insert_storage(&mut self, key: &storage::Key, value: &impl BorshSerialize) {
+        self.insert(RPC.shell().storage_value_path(key), value);
+    }
+
+    /// Read the storage keys without a fixture as missing rather than fail,
+    /// for reports that check keys a test doesn't care about
+    pub fn with_empty_storage(mut self) -> Self {
+        self.empty_storage = true;
+        self
+    }
+}
+
+impl Fixtures {
+    /// Answer of a query, replacing any earlier one
+    fn insert_query(&mut self, fixture: QueryFixture) {
+        match self
+            .queries
+            .iter_mut()
+            .find(|query| query.key == fixture.key)
+        {
+            Some(query) => *query = fixture,
+            None => self.queries.push(fixture),
+        }
+    }
+
+    fn insert_call(&mut self, fixture: CallFixture) {
+        match self
+            .calls
+            .iter_mut()
+            .find(|call| call.method == fixture.method && call.params == fixture.params)
+        {
+            Some(call) => *call = fixture,
+            None => self.calls.push(fixture),
+        }
+    }
+
+    /// The base64 response data of a query, or the code and info of the
+    /// error a node would return
+    pub fn query(&self, key: &CacheKey) -> Result<String, (u64, String)> {
+        match self.queries.iter().find(|query| &query.key == key) {
+            Some(query) if query.code == 0 => Ok(query.value.clone()),
+            Some(query) => Err((query.code, query.info.clone())),
+            None if self.empty_storage && key.path.starts_with(STORAGE_VALUE_PREFIX) => {
+                Ok(String::new())
+            }
+            None => Err((
+                1,
+                format!(
+                    "No fixture for {} with data {:?} at height {}",
+                    key.path, key.data, key.height
+                ),
+            )),
+        }
+    }
+
+    /// The result of another JSON-RPC call, or the error the node answered
+    /// with
+    pub fn call(&self, method: &str, params: &Value) -> Option<Result<&Value, &Value>> {
+        self.calls
+            .iter()
+            .find(|call| call.method == method && &call.params == params)
+            .map(|call| {
+                if call.error.is_null() {
+                    Ok(&call.result)
+                } else {
+                    Err(&call.error)
+                }
+            })
+    }
+
+    pub fn load(dir: impl AsRef<Path>) -> Self {
+        let path = dir.as_ref().join(FIXTURES_FILE);
+        let file = std::fs::File::open(&path).expect("Could not open the fixtures file");
+        serde_json::from_reader(std::io::BufReader::new(file))
+            .expect("Could not parse the fixtures file")
+    }
+
+    pub fn save(&self, dir: impl AsRef<Path>) {
+        std::fs::create_dir_all(&dir).expect("Could not create the fixtures directory");
+        let file = std::fs::File::create(dir.as_ref().join(FIXTURES_FILE))
+            .expect("Could not create the fixtures file");
+        serde_json::to_writer_pretty(file, self).expect("Could not write the fixtures file");
+    }
+}
+
+/// A local Tendermint JSON-RPC server answering requests from fixtures, so
+/// the tools can run without a node, or a proxy to a node that records or
+/// caches its answers. Point `RPC_NAMADA_UTILS` at `url`.
+pub struct MockRpc {
+    pub url: String,
+}
+
+enum Backend {
+    /// Answer from the fixtures. Requests without a fixture get an error.
+    Replay(Fixtures),
+    /// Forward to the node and record every response
+    Record {
+        upstream: String,
+        client: reqwest::Client,
+        recording: Recording,
+    },
+    /// Forward to the node, answering the `abci_query` requests it has
+    /// already answered from the cache. SQLite blocks, so the cache is only
+    /// used from blocking tasks.
+    Cache {
+        upstream: String,
+        client: reqwest::Client,
+        cache: Arc<QueryCache>,
+    },
+}
+
+impl MockRpc {
+    /// Serve the fixtures on a free local port until the runtime shuts down
+    pub async fn start(fixtures: Fixtures) -> Self {
+        Self::serve(Backend::Replay(fixtures)).await
+    }
+
+    /// Proxy to the node at `upstream`, saving its answers, errors included,
+    /// as fixtures in `dir` once the runtime shuts down. Queries are keyed by
+    /// path, data and height like the query cache, other calls by method and
+    /// params.
+    pub async fn record(upstream: &str, dir: impl AsRef<Path>) -> Self {
+        Self::serve(Backend::Record {
+            upstream: upstream.to_string(),
+            client: reqwest::Client::new(),
+            recording: Recording {
+                dir: dir.as_ref().to_path_buf(),
+                fixtures: Mutex::new(Fixtures::default()),
+            },
+        })
+        .await
+    }
+
+    /// Proxy to the node at `upstream` through the query cache
+    pub async fn cache(upstream: &str, cache: QueryCache) -> Self {
+        Self::serve(Backend::Cache {
+            upstream: upstream.to_string(),
+            client: reqwest::Client::new(),
+            cache: Arc::new(cache),
+        })
+        .await
+    }
+
+    async fn serve(backend: Backend) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Could not bind the mock RPC server");
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let backend = Arc::new(backend);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(handle(stream, backend.clone()));
+            }
+        });
+        Self { url }
+    }
+}
+
+impl Backend {
+    async fn answer(&self, body: &[u8]) -> String {
+        match self {
+            Backend::Replay(fixtures) => respond(fixtures, body).to_string(),
+            Backend::Record {
+                upstream,
+                client,
+                recording,
+            } => {
+                let response = match forward(client, upstream, body).await {
+                    Ok(response) => response,
+                    Err(e) => return upstream_error(body, e),
+                };
+                record(&mut recording.fixtures.lock().unwrap(), body, &response);
+                response
+            }
+            Backend::Cache {
+                upstream,
+                client,
+                cache,
+            } => {
+                let request: Value = serde_json::from_slice(body).unwrap_or_default();
+                let key = CacheKey::from_request(&request);
+                if let Some(key) = key.clone() {
+                    let cache = cache.clone();
+                    let cached = tokio::task::spawn_blocking(move || cache.get(&key))
+                        .await
+                        .expect("Query cache task failed");
+                    if let Some(result) = cached {
+                        return json!({ "jsonrpc": "2.0", "id": request["id"], "result": result })
+                            .to_string();
+                    }
+                }
+                let response = match forward(client, upstream, body).await {
+                    Ok(response) => response,
+                    Err(e) => return upstream_error(body, e),
+                };
+                if let Some(key) = key {
+                    let parsed: Value = serde_json::from_str(&response).unwrap_or_default();
+                    let result = parsed["result"].clone();
+                    if !result["response"].is_null()
+                        && result["response"]["code"].as_u64().unwrap_or_default() == 0
+                    {
+                        let cache = cache.clone();
+                        tokio::task::spawn_blocking(move || cache.insert(&key, &result))
+                            .await
+                            .expect("Query cache task failed");
+                    }
+                }
+                response
+            }
+        }
+    }
+}
+
+/// JSON-RPC error for a request the node could not be reached for
+fn upstream_error(body: &[u8], e: reqwest::Error) -> String {
+    let request: Value = serde_json::from_slice(body).unwrap_or_default();
+    json!({
+        "jsonrpc": "2.0",
+        "id": request["id"],
+        "error": {
+            "code": -32603,
+            "message": "Upstream error",
+            "data": e.to_string(),
+        },
+    })
+    .to_string()
+}
+
+async fn forward(
+    client: &reqwest::Client,
+    upstream: &str,
+    body: &[u8],
+) -> Result<String, reqwest::Error> {
+    client
+        .post(upstream)
+        .header("Content-Type", "application/json")
+        .body(body.to_vec())
+        .send()
+        .await?
+        .text()
+        .await
+}
+
+/// Fixtures being recorded, saved into `dir` once the server is dropped with
+/// the runtime
+struct Recording {
+    dir: PathBuf,
+    fixtures: Mutex<Fixtures>,
+}
+
+impl Drop for Recording {
+    fn drop(&mut self) {
+        let fixtures = self
+            .fixtures
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner);
+        fixtures.save(&self.dir);
+    }
+}
+
+/// Add a response, or the error the node answered with, to the fixtures.
+/// Returns whether it was added.
+fn record(fixtures: &mut Fixtures, request: &[u8], response: &str) -> bool {
+    let (Ok(request), Ok(response)) = (
+        serde_json::from_slice::<Value>(request),
+        serde_json::from_str::<Value>(response),
+    ) else {
+        return false;
+    };
+    let result = &response["result"];
+    let error = &response["error"];
+    match CacheKey::from_request(&request) {
+        Some(key) => {
+            let query = &result["response"];
+            if query.is_null() {
+                return false;
+            }
+            let field = |name: &str| query[name].as_str().unwrap_or_default().to_string();
+            fixtures.insert_query(QueryFixture {
+                key,
+                value: field("value"),
+                code: query["code"].as_u64().unwrap_or_default(),
+                info: field("info"),
+            });
+        }
+        None => {
+            let Some(method) = request["method"].as_str() else {
+                return false;
+            };
+            if result.is_null() && error.is_null() {
+                return false;
+            }
+            fixtures.insert_call(CallFixture {
+                method: method.to_string(),
+                params: request["params"].clone(),
+                result: result.clone(),
+                error: error.clone(),
+            });
+        }
+    }
+    true
+}
+
+/// Body of an HTTP request
+async fn read_body(stream: &mut TcpStream) -> Option<Vec<u8>> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let header_end = loop {
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&chunk[..read]);
+        if let Some(end) = buf.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end + 4;
+        }
+    };
+    let headers = String::from_utf8_lossy(&buf[..header_end]).to_lowercase();
+    let content_length = headers
+        .lines()
+        .find_map(|line| line.strip_prefix("content-length:"))
+        .and_then(|len| len.trim().parse::<usize>().ok())
+        .unwrap_or_default();
+    while buf.len() < header_end + content_length {
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&chunk[..read]);
+    }
+    Some(buf[header_end..header_end + content_length].to_vec())
+}
+
+/// JSON-RPC response to a request
+fn respond(fixtures: &Fixtures, body: &[u8]) -> Value {
+    let request: Value = serde_json::from_slice(body).unwrap_or_default();
+    let id = request["id"].clone();
+    let Some(key) = CacheKey::from_request(&request) else {
+        let method = request["method"].as_str().unwrap_or_default();
+        return match fixtures.call(method, &request["params"]) {
+            Some(Ok(result)) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Some(Err(error)) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+            None => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": {
+                    "code": -32601,
+                    "message": "Method not found",
+                    "data": format!("No fixture for {} with params {}", method, request["params"]),
+                },
+            }),
+        };
+    };
+
+    let (code, info, value) = match fixtures.query(&key) {
+        Ok(value) => (0, String::new(), value),
+        Err((code, info)) => (code, info, String::new()),
+    };
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "result": {
+            "response": {
+                "code": code,
+                "log": "",
+                "info": info,
+                "index": "0",
+                "key": "",
+                "value": value,
+                "proofOps": null,
+                "height": "0",
+                "codespace": "",
+            },
+        },
+    })
+}
+
+async fn handle(mut stream: TcpStream, backend: Arc<Backend>) {
+    let Some(body) = read_body(&mut stream).await else {
+        return;
+    };
+    let body = backend.answer(&body).await;
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use namada_core::chain::Epoch;
+
+    use super::*;
+
+    #[test]
+    fn test_respond_from_fixtures() {
+        let mut fixtures = Fixtures::default();
+        fixtures.insert(RPC.shell().epoch_path(), &Epoch(7));
+        let strict = fixtures.clone();
+        let fixtures = fixtures.with_empty_storage();
+
+        let query = |fixtures: &Fixtures, path: &str| {
+            let request = json!({
+                "jsonrpc": "2.0",
+                "id": "1",
+                "method": "abci_query",
+                "params": { "path": path, "data": "", "prove": false },
+            });
+            respond(fixtures, request.to_string().as_bytes())
+        };
+
+        let response = query(&fixtures, "/shell/epoch");
+        assert_eq!(response["id"], "1");
+        assert_eq!(response["result"]["response"]["code"], 0);
+        let value = response["result"]["response"]["value"].as_str().unwrap();
+        assert_eq!(
+            BASE64_STANDARD.decode(value).unwrap(),
+            Epoch(7).serialize_to_vec()
+        );
+
+        // Missing storage keys read as empty only when opted in, anything
+        // else is an error
+        let balance = "/shell/value/#tnam1q5/balance";
+        let response = query(&fixtures, balance);
+        assert_eq!(response["result"]["response"]["code"], 0);
+        assert_eq!(response["result"]["response"]["value"], "");
+        let response = query(&strict, balance);
+        assert_eq!(response["result"]["response"]["code"], 1);
+        let response = query(&fixtures, "/shell/native_token");
+        assert_eq!(response["result"]["response"]["code"], 1);
+    }
+
+    #[test]
+    fn test_record_by_data_and_height() {
+        let mut fixtures = Fixtures::default();
+        let request = |height: &str| {
+            json!({
+                "jsonrpc": "2.0",
+                "id": "1",
+                "method": "abci_query",
+                "params": { "path": "/shell/epoch", "data": "AB", "height": height },
+            })
+            .to_string()
+        };
+        let answer = json!({
+            "jsonrpc": "2.0",
+            "id": "1",
+            "result": { "response": { "code": 0, "value": "Bw==" } },
+        });
+        assert!(record(
+            &mut fixtures,
+            request("5").as_bytes(),
+            &answer.to_string()
+        ));
+        let status = json!({ "jsonrpc": "2.0", "id": "2", "method": "status", "params": {} });
+        let synced = json!({ "jsonrpc": "2.0", "id": "2", "result": { "catching_up": false } });
+        assert!(record(
+            &mut fixtures,
+            status.to_string().as_bytes(),
+            &synced.to_string()
+        ));
+
+        let response = respond(&fixtures, request("5").as_bytes());
+        assert_eq!(response["result"]["response"]["value"], "Bw==");
+        let response = respond(&fixtures, request("6").as_bytes());
+        assert_eq!(response["result"]["response"]["code"], 1);
+        let response = respond(&fixtures, status.to_string().as_bytes());
+        assert_eq!(response["id"], "2");
+        assert_eq!(response["result"]["catching_up"], false);
+
+        // Errors are recorded too, so a replay takes the same error paths
+        let missing = json!({
+            "jsonrpc": "2.0",
+            "id": "1",
+            "result": { "response": { "code": 1, "info": "No such key", "value": "" } },
+        });
+        assert!(record(
+            &mut fixtures,
+            request("7").as_bytes(),
+            &missing.to_string()
+        ));
+        let response = respond(&fixtures, request("7").as_bytes());
+        assert_eq!(response["result"]["response"]["code"], 1);
+        assert_eq!(response["result"]["response"]["info"], "No such key");
+        let block = json!({ "jsonrpc": "2.0", "id": "3", "method": "block", "params": {} });
+        let failed = json!({ "jsonrpc": "2.0", "id": "3", "error": { "code": -32603 } });
+        assert!(record(
+            &mut fixtures,
+            block.to_string().as_bytes(),
+            &failed.to_string()
+        ));
+        let response = respond(&fixtures, block.to_string().as_bytes());
+        assert_eq!(response["error"]["code"], -32603);
+    }
+}
@@ -0,0 +1,303 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use namada_sdk::account::Account;
+use namada_sdk::address::Address;
+use namada_sdk::collections::HashSet;
+use namada_sdk::io::NullIo;
+use namada_sdk::key::common;
+use namada_sdk::masp::fs::FsShieldedUtils;
+use namada_sdk::signing::{default_sign, SigningTxData};
+use namada_sdk::tendermint_rpc::HttpClient;
+use namada_sdk::tx::{ProcessTxResponse, SignatureIndex, Tx};
+use namada_sdk::wallet::fs::FsWalletUtils;
+use namada_sdk::{args, rpc, Namada, NamadaImpl};
+
+/// Offline signing of txs from multisig accounts, shared by the commands that
+/// submit txs
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct MultisigArgs {
+    /// Write the built txs into this directory for offline signing instead
+    /// of submitting them
+    #[arg(long = "dump-tx", conflicts_with = "tx_file")]
+    pub dump_tx: Option<PathBuf>,
+
+    /// Sign and submit a tx written by an earlier `--dump-tx` run instead of
+    /// building a new one
+    #[arg(long = "tx-file")]
+    pub tx_file: Option<String>,
+
+    /// Signature files produced offline by the other signers for the
+    /// `--tx-file` tx
+    #[arg(long, value_delimiter = ',', requires = "tx_file")]
+    pub signatures: Vec<String>,
+}
+
+impl MultisigArgs {
+    pub fn signature_files(&self) -> Vec<SignatureFile> {
+        load_signature_files(&self.signatures)
+    }
+
+    /// The `--tx-file` tx with its signing data, if one was given
+    pub fn dumped_tx(
+        &self,
+        account: &Account,
+        wallet_keys: &[common::PublicKey],
+    ) -> Option<(Tx, SigningTxData)> {
+        let tx = read_tx_file(self.tx_file.as_ref()?);
+        let signing_data = signing_data_for_dumped_tx(&tx, account, wallet_keys);
+        Some((tx, signing_data))
+    }
+}
+
+/// Signatures over a tx's raw header, produced offline by other signers
+/// (e.g. with `namadac utils sign-offline`).
+pub struct SignatureFile {
+    pub path: String,
+    pub bytes: Vec<u8>,
+    pub signature: SignatureIndex,
+}
+
+pub fn load_signature_files(paths: &[String]) -> Vec<SignatureFile> {
+    paths
+        .iter()
+        .map(|path| {
+            let bytes = std::fs::read(path).expect("Could not read signature file");
+            let signature = SignatureIndex::try_from_json_bytes(&bytes)
+                .expect("Could not parse signature file");
+            SignatureFile {
+                path: path.clone(),
+                bytes,
+                signature,
+            }
+        })
+        .collect()
+}
+
+/// Query the public keys and threshold of the source account. An implicit
+/// account is a single-key account with a threshold of 1, whose key is taken
+/// from the wallet, or else the key revealed on chain.
+pub async fn query_signing_account(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    source: &Address,
+) -> Account {
+    match source {
+        Address::Established(_) => rpc::get_account_info(&sdk.client, source)
+            .await
+            .expect("Query account info error")
+            .expect("Source account does not exist on chain"),
+        Address::Implicit(pkh) => {
+            let wallet_key = sdk.wallet().await.find_public_key_from_implicit_addr(pkh).ok();
+            let pk = match wallet_key {
+                Some(pk) => pk,
+                None => query_account_keys(sdk, source)
+                    .await
+                    .into_iter()
+                    .next()
+                    .unwrap_or_else(|| {
+                        panic!("The key of {} is neither in the wallet nor revealed", source)
+                    }),
+            };
+            Account {
+                public_keys_map: [pk].into_iter().collect(),
+                threshold: 1,
+                address: source.clone(),
+            }
+        }
+        Address::Internal(_) => panic!("{} is an internal address and cannot sign", source),
+    }
+}
+
+/// The public keys of the account known on chain, in index order: the keys
+/// of an established account, or the revealed key of an implicit one
+pub async fn query_account_keys(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    address: &Address,
+) -> Vec<common::PublicKey> {
+    let Some(account) = rpc::get_account_info(&sdk.client, address)
+        .await
+        .expect("Query account info error")
+    else {
+        return Vec::new();
+    };
+    let mut keys = account.public_keys_map.idx_to_pk.into_iter().collect::<Vec<_>>();
+    keys.sort_by_key(|(idx, _)| *idx);
+    keys.into_iter().map(|(_, pk)| pk).collect()
+}
+
+/// The distinct account keys that will sign, either from the wallet or from
+/// signature files. Keys that don't belong to the account are reported and
+/// ignored.
+pub fn collect_signers(
+    account: &Account,
+    wallet_keys: &[common::PublicKey],
+    signature_files: &[SignatureFile],
+) -> HashSet<common::PublicKey> {
+    let mut signers = HashSet::new();
+    for pk in wallet_keys {
+        if account.public_keys_map.get_index_from_public_key(pk).is_some() {
+            signers.insert(pk.clone());
+        } else {
+            println!("Wallet key {} is not a key of {}, ignoring", pk, account.address);
+        }
+    }
+    for file in signature_files {
+        let pk = &file.signature.pubkey;
+        if account.public_keys_map.get_index_from_public_key(pk).is_some() {
+            signers.insert(pk.clone());
+        } else {
+            println!(
+                "Signature in {} is from {}, which is not a key of {}, ignoring",
+                file.path, pk, account.address
+            );
+        }
+    }
+    signers
+}
+
+/// Whether the wallet keys and signature files meet the threshold of the
+/// account. `signer` names the account in the printed count, e.g. `Source
+/// tnam1...`.
+pub fn check_threshold(
+    signer: &str,
+    account: &Account,
+    wallet_keys: &[common::PublicKey],
+    signature_files: &[SignatureFile],
+) -> bool {
+    let signers = collect_signers(account, wallet_keys, signature_files);
+    println!(
+        "{} has {} of {} required signatures",
+        signer,
+        signers.len(),
+        account.threshold
+    );
+    signers.len() >= account.threshold as usize
+}
+
+/// Rebuild the signing data of a tx that was dumped by an earlier run, using
+/// the wallet keys of the source account and the fee payer from the wrapper
+/// header.
+pub fn signing_data_for_dumped_tx(
+    tx: &Tx,
+    account: &Account,
+    wallet_keys: &[common::PublicKey],
+) -> SigningTxData {
+    let fee_payer = tx
+        .header
+        .wrapper()
+        .expect("Dumped tx is not a wrapper tx")
+        .pk;
+    SigningTxData {
+        owner: Some(account.address.clone()),
+        public_keys: wallet_keys
+            .iter()
+            .filter(|pk| account.public_keys_map.get_index_from_public_key(pk).is_some())
+            .cloned()
+            .collect(),
+        threshold: account.threshold,
+        account_public_keys_map: Some(account.public_keys_map.clone()),
+        fee_payer,
+        shielded_hash: None,
+    }
+}
+
+pub fn read_tx_file(path: &str) -> Tx {
+    let bytes = std::fs::read(path).expect("Could not read tx file");
+    Tx::try_from_json_bytes(&bytes).expect("Could not parse tx file")
+}
+
+/// Write the (unsigned) tx as JSON into `dir` so that the other signers of a
+/// multisig account can sign it offline. Returns the path of the tx file.
+pub fn dump_tx_for_signers(dir: &Path, tx: &Tx) -> PathBuf {
+    std::fs::create_dir_all(dir).expect("Could not create tx output directory");
+    let path = dir.join(format!(
+        "{}.tx",
+        tx.header_hash().to_string().to_lowercase()
+    ));
+    let file = File::create(&path).expect("Could not create tx file");
+    tx.to_writer_json(file).expect("Could not write tx file");
+    path
+}
+
+/// Sign the tx with the wallet keys and the supplied signature files. Fails
+/// unless the account threshold is met.
+pub async fn sign_tx(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    tx: &mut Tx,
+    tx_args: &args::Tx,
+    signing_data: SigningTxData,
+    signature_files: &[SignatureFile],
+) {
+    let tx_args = args::Tx {
+        signatures: signature_files.iter().map(|file| file.bytes.clone()).collect(),
+        ..tx_args.clone()
+    };
+
+    sdk.sign(tx, &tx_args, signing_data, default_sign, ())
+        .await
+        .expect("unable to sign tx");
+}
+
+/// Sign the tx with the wallet keys and the supplied signature files, and
+/// submit it. Nothing is submitted unless the account threshold is met.
+/// Returns the response, or `None` if submitting failed.
+pub async fn sign_and_submit(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    mut tx: Tx,
+    tx_args: &args::Tx,
+    signing_data: SigningTxData,
+    signature_files: &[SignatureFile],
+) -> Option<ProcessTxResponse> {
+    sign_tx(sdk, &mut tx, tx_args, signing_data, signature_files).await;
+
+    match sdk.submit(tx, tx_args).await {
+        Ok(res) => {
+            println!("Tx result: {:?}", res);
+            Some(res)
+        }
+        Err(e) => {
+            println!("\n\nTx error: {:?}\n\n", e);
+            None
+        }
+    }
+}
+
+/// Write the tx for offline signing with `--dump-tx`, or else sign and submit
+/// it if the threshold is met, with the `--signatures` if it is the
+/// `--tx-file` tx. `rerun_args` go into the hint printed for the `--tx-file`
+/// rerun, e.g. `--addresses tnam1... `. Returns the response, or `None` if
+/// nothing was submitted.
+pub async fn submit_or_dump(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    tx: Tx,
+    tx_args: &args::Tx,
+    signing_data: SigningTxData,
+    threshold_met: bool,
+    multisig: &MultisigArgs,
+    rerun_args: &str,
+) -> Option<ProcessTxResponse> {
+    if let Some(dir) = &multisig.dump_tx {
+        let path = dump_tx_for_signers(dir, &tx);
+        println!(
+            "Tx written to {} for offline signing. Rerun with {}--tx-file {} --signatures \
+             <files> once signed.",
+            path.display(),
+            rerun_args,
+            path.display()
+        );
+        return None;
+    }
+    if !threshold_met {
+        println!(
+            "Threshold not met, not submitting. Use --dump-tx to sign offline, or pass the \
+             --signatures of the other signers."
+        );
+        return None;
+    }
+    // Offline signatures only ever sign a dumped tx, never a new one
+    let signature_files = match multisig.tx_file {
+        Some(_) => multisig.signature_files(),
+        None => Vec::new(),
+    };
+    sign_and_submit(sdk, tx, tx_args, signing_data, &signature_files).await
+}
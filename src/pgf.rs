@@ -0,0 +1,218 @@
+use namada_core::chain::Epoch;
+use namada_core::token;
+use namada_governance::pgf::parameters::PgfParameters;
+use namada_governance::pgf::storage::steward::StewardDetail;
+use namada_governance::storage::proposal::{
+    PGFAction, PGFTarget, ProposalType, StoragePgfFunding, StorageProposal,
+};
+use namada_governance::utils::TallyResult;
+use namada_parameters::storage::get_epochs_per_year_key;
+use namada_sdk::io::NullIo;
+use namada_sdk::masp::fs::FsShieldedUtils;
+use namada_sdk::tendermint_rpc::HttpClient;
+use namada_sdk::wallet::fs::FsWalletUtils;
+use namada_sdk::{rpc, NamadaImpl};
+use namada_token::Dec;
+
+use crate::gov::query_proposals;
+
+/// PGF state and the inputs of its inflation
+pub struct PgfState {
+    pub parameters: PgfParameters,
+    pub stewards: Vec<StewardDetail>,
+    /// Continuous fundings, in the order they are paid
+    pub fundings: Vec<StoragePgfFunding>,
+    pub epochs_per_year: u64,
+    pub native_supply: token::Amount,
+    /// Annual PoS inflation rate
+    pub pos_inflation_rate: Dec,
+}
+
+/// A PGF action of a funding proposal
+pub struct PgfPayment {
+    pub proposal: StorageProposal,
+    pub action: PGFAction,
+    /// `None` until voting ends
+    pub passed: Option<bool>,
+}
+
+pub async fn query_pgf_state(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+) -> PgfState {
+    let parameters = rpc::query_pgf_parameters(&sdk.client).await;
+    let stewards = rpc::query_pgf_stewards(&sdk.client)
+        .await
+        .expect("Query PGF stewards error");
+    let mut fundings = rpc::query_pgf_fundings(&sdk.client)
+        .await
+        .expect("Query PGF fundings error");
+    // Fundings are paid by oldest proposal first
+    fundings.sort_by_key(|funding| funding.id);
+    let epochs_per_year: u64 = rpc::query_storage_value(&sdk.client, &get_epochs_per_year_key())
+        .await
+        .expect("Query epochs per year error");
+    let native_supply = rpc::get_effective_native_supply(&sdk.client)
+        .await
+        .expect("Query native supply error");
+    let pos_inflation_rate = rpc::get_staking_rewards_rate(&sdk.client)
+        .await
+        .expect("Query staking rewards rate error")
+        .inflation_rate;
+
+    PgfState {
+        parameters,
+        stewards,
+        fundings,
+        epochs_per_year,
+        native_supply,
+        pos_inflation_rate,
+    }
+}
+
+/// Continuous and retro payments of all PGF funding proposals
+pub async fn query_pgf_payments(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+) -> Vec<PgfPayment> {
+    let current_epoch = rpc::query_epoch(&sdk.client)
+        .await
+        .expect("Query epoch error");
+    let mut payments = Vec::new();
+    for proposal in query_proposals(sdk).await {
+        let ProposalType::PGFPayment(actions) = &proposal.r#type else {
+            continue;
+        };
+        let passed = if proposal.voting_end_epoch <= current_epoch {
+            rpc::query_proposal_result(&sdk.client, proposal.id)
+                .await
+                .expect("Query proposal result error")
+                .map(|result| matches!(result.result, TallyResult::Passed))
+        } else {
+            None
+        };
+        for action in actions {
+            payments.push(PgfPayment {
+                proposal: proposal.clone(),
+                action: action.clone(),
+                passed,
+            });
+        }
+    }
+    payments
+}
+
+impl PgfPayment {
+    /// Whether the proposal is set to change the PGF payments after
+    /// `current_epoch`, unless it was rejected
+    pub fn is_upcoming(&self, current_epoch: Epoch) -> bool {
+        self.proposal.activation_epoch > current_epoch && self.passed != Some(false)
+    }
+}
+
+impl PgfState {
+    /// Amount minted into the PGF account every epoch
+    pub fn pgf_inflation(&self, supply: token::Amount) -> token::Amount {
+        supply
+            .mul_floor(self.parameters.pgf_inflation_rate)
+            .unwrap()
+            .checked_div_u64(self.epochs_per_year)
+            .unwrap_or_default()
+    }
+
+    /// Amount minted to the stewards every epoch
+    pub fn stewards_inflation(&self, supply: token::Amount) -> token::Amount {
+        supply
+            .mul_floor(self.parameters.stewards_inflation_rate)
+            .unwrap()
+            .checked_div_u64(self.epochs_per_year)
+            .unwrap_or_default()
+    }
+
+    /// Effective supply and PGF balance after one epoch. The PGF inflation is
+    /// minted and each continuous funding is paid if the balance covers it, as
+    /// done by the protocol. The effective supply, which excludes the PGF
+    /// balance, grows with `pos_inflation`, the steward inflation and the paid
+    /// fundings.
+    pub fn step_epoch(
+        &self,
+        supply: token::Amount,
+        balance: token::Amount,
+        pos_inflation: token::Amount,
+    ) -> (token::Amount, token::Amount) {
+        let mut balance = balance.checked_add(self.pgf_inflation(supply)).unwrap();
+        let mut paid = token::Amount::zero();
+        for funding in &self.fundings {
+            if let Some(left) = balance.checked_sub(funding.detail.amount()) {
+                balance = left;
+                paid = paid.checked_add(funding.detail.amount()).unwrap();
+            }
+        }
+        let stewards_inflation = self.stewards_inflation(supply);
+        let supply = supply
+            .checked_add(pos_inflation)
+            .and_then(|supply| supply.checked_add(stewards_inflation))
+            .and_then(|supply| supply.checked_add(paid))
+            .unwrap();
+        (supply, balance)
+    }
+
+    /// PGF balance at the end of each of the next `epochs` epochs, with the
+    /// PoS inflation at the current rate
+    pub fn project_balance(
+        &self,
+        balance: token::Amount,
+        from_epoch: Epoch,
+        epochs: u64,
+    ) -> Vec<(Epoch, token::Amount)> {
+        let mut supply = self.native_supply;
+        let mut balance = balance;
+        let mut projection = Vec::new();
+        for epoch in 1..=epochs {
+            let pos_inflation = supply
+                .mul_floor(self.pos_inflation_rate)
+                .unwrap()
+                .checked_div_u64(self.epochs_per_year)
+                .unwrap_or_default();
+            (supply, balance) = self.step_epoch(supply, balance, pos_inflation);
+            projection.push((Epoch(from_epoch.0 + epoch), balance));
+        }
+        projection
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use namada_governance::storage::proposal::PGFInternalTarget;
+
+    use super::*;
+
+    #[test]
+    fn test_project_balance() {
+        let target = namada_core::address::gen_deterministic_established_address("target");
+        let funding = |amount: u64, id: u64| StoragePgfFunding {
+            detail: PGFTarget::Internal(PGFInternalTarget {
+                target: target.clone(),
+                amount: token::Amount::from(amount),
+            }),
+            id,
+        };
+        let state = PgfState {
+            parameters: PgfParameters {
+                pgf_inflation_rate: Dec::new(1, 1).unwrap(),
+                stewards_inflation_rate: Dec::zero(),
+                ..PgfParameters::default()
+            },
+            stewards: vec![],
+            fundings: vec![funding(60, 0), funding(70, 1)],
+            epochs_per_year: 10,
+            native_supply: token::Amount::from(1000u64),
+            pos_inflation_rate: Dec::zero(),
+        };
+
+        // 10 is minted per epoch. The second funding is skipped while the
+        // balance can't cover it.
+        let projection = state.project_balance(token::Amount::from(100u64), Epoch(5), 2);
+        assert_eq!(projection[0], (Epoch(6), token::Amount::from(50u64)));
+        // The paid funding grew the supply to 1060, so 10.6 is floored to 10
+        assert_eq!(projection[1], (Epoch(7), token::Amount::zero()));
+    }
+}
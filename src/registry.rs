@@ -0,0 +1,169 @@
+use namada_core::token::{self, DenominatedAmount, Denomination};
+use namada_sdk::address::Address;
+use namada_sdk::wallet::fs::FsWalletUtils;
+use namada_sdk::wallet::Wallet;
+use serde::Deserialize;
+
+use crate::{get_address_from_ibc_denom, resolve_address, ConfigParams};
+
+/// A `[[tokens]]` entry of the config file. IBC tokens are given by their
+/// denom trace, other tokens by address or wallet alias.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TokenConfig {
+    pub symbol: String,
+    pub trace: Option<String>,
+    pub address: Option<String>,
+    pub decimals: u8,
+    pub origin_chain: String,
+}
+
+impl TokenConfig {
+    /// An entry of the old `ibc_tokens` list of denom traces, named after
+    /// its trace and with 6 decimals, as it was printed before the registry
+    pub fn from_legacy_trace(trace: &str) -> Self {
+        Self {
+            symbol: trace.to_string(),
+            trace: Some(trace.to_string()),
+            address: None,
+            decimals: 6,
+            origin_chain: String::new(),
+        }
+    }
+}
+
+/// Every `[[tokens]]` entry needs exactly one of `trace` or `address`
+pub fn check_token_configs(tokens: &[TokenConfig]) -> Result<(), String> {
+    for (idx, token) in tokens.iter().enumerate() {
+        if token.trace.is_some() == token.address.is_some() {
+            return Err(format!(
+                "[[tokens]] entry {} ({}) needs exactly one of `trace` or `address`",
+                idx + 1,
+                token.symbol
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// A registry entry with its on-chain address resolved
+#[derive(Debug, Clone)]
+pub struct RegisteredToken {
+    pub symbol: String,
+    pub address: Address,
+    pub trace: Option<String>,
+    pub decimals: u8,
+    pub origin_chain: String,
+}
+
+impl RegisteredToken {
+    pub fn is_ibc(&self) -> bool {
+        self.trace.is_some()
+    }
+
+    /// The amount with this token's decimals, without the symbol
+    pub fn format_amount(&self, amount: token::Amount) -> String {
+        DenominatedAmount::new(amount, Denomination(self.decimals)).to_string()
+    }
+}
+
+/// The tokens known to the tools, in config file order
+#[derive(Debug, Clone, Default)]
+pub struct TokenRegistry {
+    tokens: Vec<RegisteredToken>,
+}
+
+impl TokenRegistry {
+    pub fn tokens(&self) -> &[RegisteredToken] {
+        &self.tokens
+    }
+
+    pub fn ibc_tokens(&self) -> impl Iterator<Item = &RegisteredToken> {
+        self.tokens.iter().filter(|token| token.is_ibc())
+    }
+
+    pub fn get(&self, address: &Address) -> Option<&RegisteredToken> {
+        self.tokens.iter().find(|token| &token.address == address)
+    }
+
+    pub fn get_by_symbol(&self, symbol: &str) -> Option<&RegisteredToken> {
+        self.tokens
+            .iter()
+            .find(|token| token.symbol.eq_ignore_ascii_case(symbol))
+    }
+
+    /// The registered symbol, or the address itself for unknown tokens
+    pub fn symbol(&self, address: &Address) -> String {
+        self.get(address)
+            .map(|token| token.symbol.clone())
+            .unwrap_or_else(|| address.to_string())
+    }
+
+    /// Format as `<amount> <symbol>`. Amounts of unknown tokens are shown raw.
+    pub fn format_amount(&self, address: &Address, amount: token::Amount) -> String {
+        match self.get(address) {
+            Some(token) => format!("{} {}", token.format_amount(amount), token.symbol),
+            None => format!("{} {}", amount, address),
+        }
+    }
+}
+
+pub fn get_token_registry(wallet: &Wallet<FsWalletUtils>, config: &ConfigParams) -> TokenRegistry {
+    let tokens = config
+        .tokens
+        .iter()
+        .map(|token| {
+            let address = match (&token.trace, &token.address) {
+                (Some(trace), None) => get_address_from_ibc_denom(trace),
+                (None, Some(address)) => resolve_address(wallet, &config.address_book, address),
+                _ => unreachable!("checked by check_token_configs when loading the config"),
+            };
+            RegisteredToken {
+                symbol: token.symbol.clone(),
+                address,
+                trace: token.trace.clone(),
+                decimals: token.decimals,
+                origin_chain: token.origin_chain.clone(),
+            }
+        })
+        .collect();
+    TokenRegistry { tokens }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_registry_lookup_and_format() {
+        let osmo = get_address_from_ibc_denom("transfer/channel-1/uosmo");
+        let registry = TokenRegistry {
+            tokens: vec![RegisteredToken {
+                symbol: String::from("OSMO"),
+                address: osmo.clone(),
+                trace: Some(String::from("transfer/channel-1/uosmo")),
+                decimals: 6,
+                origin_chain: String::from("osmosis-1"),
+            }],
+        };
+        assert_eq!(registry.symbol(&osmo), "OSMO");
+        assert!(registry.get_by_symbol("osmo").is_some());
+        assert_eq!(registry.ibc_tokens().count(), 1);
+        assert_eq!(
+            registry.format_amount(&osmo, token::Amount::from(1_500_000u64)),
+            "1.5 OSMO"
+        );
+
+        let mut tokens = vec![TokenConfig::from_legacy_trace("transfer/channel-1/uosmo")];
+        assert!(check_token_configs(&tokens).is_ok());
+        tokens[0].address = Some(String::from("nam"));
+        assert!(check_token_configs(&tokens)
+            .unwrap_err()
+            .starts_with("[[tokens]] entry 1 (transfer/channel-1/uosmo)"));
+
+        let unknown =
+            Address::from_str("tnam1qq5rtyhdmj4fnkstlvsj5zd0u79t84xwugfjxjj5").unwrap();
+        assert_eq!(registry.symbol(&unknown), unknown.to_string());
+    }
+}
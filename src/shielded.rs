@@ -0,0 +1,208 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use namada_core::masp::ExtendedViewingKey;
+use namada_core::token;
+use namada_parameters::storage::{get_epochs_per_year_key, get_masp_epoch_multiplier_key};
+use namada_sdk::address::Address;
+use namada_sdk::control_flow::install_shutdown_signal;
+use namada_sdk::error::{Error, QueryError};
+use namada_sdk::io::{DevNullProgressBar, NullIo};
+use namada_sdk::masp::fs::FsShieldedUtils;
+use namada_sdk::masp::shielded_wallet::ShieldedApi;
+use namada_sdk::masp::{LedgerMaspClient, MaspLocalTaskEnv, ShieldedSyncConfig};
+use namada_sdk::masp_primitives::sapling::ViewingKey;
+use namada_sdk::tendermint_rpc::HttpClient;
+use namada_sdk::wallet::fs::FsWalletUtils;
+use namada_sdk::wallet::{DatedKeypair, Wallet};
+use namada_sdk::{rpc, Namada, NamadaImpl};
+use namada_token::storage_key::{
+    masp_kd_gain_key, masp_kp_gain_key, masp_last_inflation_key, masp_last_locked_amount_key,
+    masp_locked_amount_target_key, masp_max_reward_rate_key,
+};
+use namada_token::Dec;
+
+use crate::ConfigParams;
+
+/// A viewing key from the config, with the label it was given there
+pub struct ConfiguredViewingKey {
+    pub label: String,
+    pub key: DatedKeypair<ViewingKey>,
+}
+
+/// The config's `viewing_keys`, either raw `zvknam` keys or wallet aliases.
+/// Only wallet aliases carry a birthday to start syncing from.
+pub fn get_viewing_keys(
+    wallet: &Wallet<FsWalletUtils>,
+    config: &ConfigParams,
+) -> Vec<ConfiguredViewingKey> {
+    config
+        .viewing_keys
+        .iter()
+        .map(|vk| {
+            let (key, birthday) = if vk.starts_with("zvknam") {
+                let key = ExtendedViewingKey::from_str(vk).expect("Could not parse viewing key");
+                (key, None)
+            } else {
+                let key = *wallet
+                    .find_viewing_key(vk)
+                    .expect("Could not find viewing key in wallet");
+                (key, wallet.find_birthday(vk).copied())
+            };
+            ConfiguredViewingKey {
+                label: vk.clone(),
+                key: DatedKeypair::new(key.as_viewing_key(), birthday),
+            }
+        })
+        .collect()
+}
+
+/// Fetch and trial-decrypt new MASP notes for the given keys. The shielded
+/// context is loaded from and saved back to `./masp`, so later runs only sync
+/// the blocks since the last one.
+pub async fn shielded_sync(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    viewing_keys: &[ConfiguredViewingKey],
+) {
+    let vks = viewing_keys
+        .iter()
+        .map(|vk| vk.key.clone())
+        .collect::<Vec<_>>();
+
+    let mut shielded = sdk.shielded_mut().await;
+    // There is nothing to load before the first sync
+    if let Err(e) = shielded.load().await {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            panic!("Could not load shielded context: {}", e);
+        }
+    }
+
+    let config = ShieldedSyncConfig::builder()
+        .client(LedgerMaspClient::new(
+            sdk.client.clone(),
+            100,
+            Duration::from_millis(5),
+        ))
+        .fetched_tracker(DevNullProgressBar)
+        .scanned_tracker(DevNullProgressBar)
+        .applied_tracker(DevNullProgressBar)
+        .shutdown_signal(install_shutdown_signal(false))
+        .build();
+    let env = MaspLocalTaskEnv::new(4).expect("Could not create MASP task environment");
+
+    shielded
+        .sync(env, config, None, &[], &vks)
+        .await
+        .expect("Shielded sync error");
+    shielded.save().await.expect("Could not save shielded context");
+}
+
+/// Shielded balance of each token held by the viewing key, with older
+/// reward-bearing notes converted to the current MASP epoch.
+pub async fn get_shielded_balances(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    viewing_key: &ViewingKey,
+    tokens: &[Address],
+) -> Vec<(Address, token::Amount)> {
+    let masp_epoch = rpc::query_masp_epoch(&sdk.client)
+        .await
+        .expect("Query MASP epoch error");
+
+    let mut shielded = sdk.shielded_mut().await;
+    shielded
+        .precompute_asset_types(&sdk.client, tokens.iter().collect())
+        .await
+        .expect("Could not precompute the token asset types");
+
+    let Some(balance) = shielded
+        .compute_exchanged_balance(&sdk.client, &NullIo, viewing_key)
+        .await
+        .expect("Could not compute shielded balance")
+    else {
+        return tokens
+            .iter()
+            .map(|token| (token.clone(), token::Amount::zero()))
+            .collect();
+    };
+    let decoded = shielded
+        .decode_combine_sum_to_epoch(&sdk.client, balance, masp_epoch)
+        .await
+        .0;
+
+    tokens
+        .iter()
+        .map(|token| (token.clone(), token::Amount::from_change(decoded.get(token))))
+        .collect()
+}
+
+/// MASP reward parameters of a token together with the PD-controller state
+/// left by the last MASP epoch
+#[derive(Debug, Clone)]
+pub struct MaspRewardInfo {
+    pub max_reward_rate: Dec,
+    pub kp_gain: Dec,
+    pub kd_gain: Dec,
+    pub locked_amount_target: token::Amount,
+    pub last_locked_amount: token::Amount,
+    pub last_inflation: token::Amount,
+}
+
+impl MaspRewardInfo {
+    /// Rewards minted in the last MASP epoch per unit of locked token,
+    /// annualized. Zero if nothing is locked.
+    pub fn reward_rate(&self, masp_epochs_per_year: u64) -> Dec {
+        if self.last_locked_amount.is_zero() {
+            return Dec::zero();
+        }
+        Dec::try_from(self.last_inflation)
+            .unwrap()
+            .checked_div(Dec::try_from(self.last_locked_amount).unwrap())
+            .unwrap()
+            .checked_mul(masp_epochs_per_year)
+            .unwrap()
+    }
+}
+
+/// Number of MASP epochs in a year, i.e. the epochs per year divided by the
+/// MASP epoch multiplier
+pub async fn query_masp_epochs_per_year(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+) -> u64 {
+    let epochs_per_year: u64 = rpc::query_storage_value(&sdk.client, &get_epochs_per_year_key())
+        .await
+        .expect("Query epochs per year error");
+    let masp_epoch_multiplier: u64 =
+        rpc::query_storage_value(&sdk.client, &get_masp_epoch_multiplier_key())
+            .await
+            .expect("Query MASP epoch multiplier error");
+    epochs_per_year / masp_epoch_multiplier
+}
+
+/// Query the MASP reward parameters of the token. Returns `None` for tokens
+/// that have no shielded rewards configured, i.e. no max reward rate.
+pub async fn query_masp_reward_info(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    token: &Address,
+) -> Result<Option<MaspRewardInfo>, Error> {
+    let client = &sdk.client;
+    let max_reward_rate =
+        match rpc::query_storage_value(client, &masp_max_reward_rate_key(token)).await {
+            Ok(rate) => rate,
+            Err(Error::Query(QueryError::NoSuchKey(_))) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+    // The other parameters are written with the max reward rate
+    Ok(Some(MaspRewardInfo {
+        max_reward_rate,
+        kp_gain: rpc::query_storage_value(client, &masp_kp_gain_key(token)).await?,
+        kd_gain: rpc::query_storage_value(client, &masp_kd_gain_key(token)).await?,
+        locked_amount_target: rpc::query_storage_value(
+            client,
+            &masp_locked_amount_target_key(token),
+        )
+        .await?,
+        last_locked_amount: rpc::query_storage_value(client, &masp_last_locked_amount_key(token))
+            .await?,
+        last_inflation: rpc::query_storage_value(client, &masp_last_inflation_key(token)).await?,
+    }))
+}
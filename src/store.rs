@@ -0,0 +1,481 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use namada_core::token::{self, DenominatedAmount, Denomination};
+use namada_proof_of_stake::rewards::PosRewardsRates;
+use namada_sdk::address::Address;
+use namada_sdk::io::NullIo;
+use namada_sdk::masp::fs::FsShieldedUtils;
+use namada_sdk::rpc::{self, enriched_bonds_and_unbonds};
+use namada_sdk::tendermint_rpc::HttpClient;
+use namada_sdk::wallet::fs::FsWalletUtils;
+use namada_sdk::{Namada, NamadaImpl};
+use namada_token::Dec;
+use rusqlite::{params, Connection};
+
+use crate::address_book::AddressBook;
+use crate::registry::TokenRegistry;
+use crate::{
+    get_genesis_categories, get_public_alloc_balance, get_public_alloc_stake, query_top_validators,
+};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS snapshots (
+    height INTEGER PRIMARY KEY,
+    epoch INTEGER NOT NULL,
+    time TEXT NOT NULL,
+    chain_id TEXT NOT NULL,
+    total_staked TEXT NOT NULL,
+    native_supply TEXT NOT NULL,
+    pgf_balance TEXT NOT NULL,
+    gov_balance TEXT NOT NULL,
+    staking_rewards_rate TEXT NOT NULL,
+    pos_inflation_rate TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS snapshots_epoch ON snapshots (epoch);
+CREATE TABLE IF NOT EXISTS tokens (
+    height INTEGER NOT NULL REFERENCES snapshots (height),
+    symbol TEXT NOT NULL,
+    decimals INTEGER NOT NULL,
+    total_supply TEXT NOT NULL,
+    masp_balance TEXT NOT NULL,
+    PRIMARY KEY (height, symbol)
+);
+CREATE TABLE IF NOT EXISTS validators (
+    height INTEGER NOT NULL REFERENCES snapshots (height),
+    rank INTEGER NOT NULL,
+    address TEXT NOT NULL,
+    name TEXT,
+    stake TEXT NOT NULL,
+    PRIMARY KEY (height, address)
+);
+CREATE TABLE IF NOT EXISTS categories (
+    height INTEGER NOT NULL REFERENCES snapshots (height),
+    category TEXT NOT NULL,
+    genesis_balance TEXT NOT NULL,
+    staked TEXT NOT NULL,
+    PRIMARY KEY (height, category)
+);
+";
+
+/// Chain state at one block: what `chain_info`, `token_metrics`,
+/// `get_top_validators` and `genesis_staking` report
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub height: u64,
+    pub epoch: u64,
+    pub time: String,
+    pub chain_id: String,
+    pub total_staked: token::Amount,
+    /// Effective native supply, which excludes the PGF balance
+    pub native_supply: token::Amount,
+    pub pgf_balance: token::Amount,
+    pub gov_balance: token::Amount,
+    pub staking_rewards_rate: Dec,
+    pub pos_inflation_rate: Dec,
+    pub tokens: Vec<TokenSnapshot>,
+    pub validators: Vec<ValidatorSnapshot>,
+    pub categories: Vec<CategorySnapshot>,
+}
+
+/// Supply of an IBC token from the registry
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenSnapshot {
+    pub symbol: String,
+    pub decimals: u8,
+    pub total_supply: token::Amount,
+    pub masp_balance: token::Amount,
+}
+
+impl TokenSnapshot {
+    pub fn format_amount(&self, amount: token::Amount) -> String {
+        DenominatedAmount::new(amount, Denomination(self.decimals)).to_string()
+    }
+}
+
+/// A consensus validator, ranked by stake
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidatorSnapshot {
+    pub rank: u32,
+    pub address: String,
+    pub name: Option<String>,
+    pub stake: token::Amount,
+}
+
+/// Stake of a genesis allocation category
+#[derive(Debug, Clone, PartialEq)]
+pub struct CategorySnapshot {
+    pub category: String,
+    pub genesis_balance: token::Amount,
+    pub staked: token::Amount,
+}
+
+impl Snapshot {
+    pub fn staked_ratio(&self) -> Dec {
+        Dec::try_from(self.total_staked)
+            .unwrap()
+            .checked_div(Dec::try_from(self.native_supply).unwrap())
+            .unwrap_or_default()
+    }
+}
+
+/// Bonded stake of the given delegators at `epoch`
+async fn query_stake(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    epoch: namada_core::chain::Epoch,
+    delegators: impl IntoIterator<Item = Address>,
+) -> Result<token::Amount, String> {
+    let mut staked = token::Amount::zero();
+    for delegator in delegators {
+        let bonds = enriched_bonds_and_unbonds(&sdk.client, epoch, &Some(delegator), &None)
+            .await
+            .map_err(|e| format!("Query bonds error: {}", e))?;
+        staked = staked
+            .checked_add(bonds.bonds_total_active().unwrap())
+            .unwrap();
+    }
+    Ok(staked)
+}
+
+/// Query everything a snapshot holds. The `num_vals` largest consensus
+/// validators are recorded; the genesis categories need the files
+/// `genesis_staking` reads and are skipped with `genesis = false`.
+pub async fn take_snapshot(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    registry: &TokenRegistry,
+    book: &AddressBook,
+    chain_id: &str,
+    num_vals: usize,
+    genesis: bool,
+) -> Result<Snapshot, String> {
+    let last_block = rpc::query_block(&sdk.client)
+        .await
+        .map_err(|e| format!("Query block error: {}", e))?
+        .ok_or("No block committed yet")?;
+    let epoch = rpc::query_epoch(&sdk.client)
+        .await
+        .map_err(|e| format!("Query epoch error: {}", e))?;
+
+    let native_token = sdk.native_token();
+    let total_staked = rpc::get_total_staked_tokens(&sdk.client, epoch)
+        .await
+        .map_err(|e| format!("Query total stake error: {}", e))?;
+    let native_supply = rpc::get_effective_native_supply(&sdk.client)
+        .await
+        .map_err(|e| format!("Query native supply error: {}", e))?;
+    let PosRewardsRates {
+        staking_rewards_rate,
+        inflation_rate,
+    } = rpc::get_staking_rewards_rate(&sdk.client)
+        .await
+        .map_err(|e| format!("Query staking rewards rate error: {}", e))?;
+
+    let mut balances = Vec::new();
+    for alias in ["pgf", "governance"] {
+        let address = sdk.wallet().await.find_address(alias).unwrap().into_owned();
+        balances.push(
+            rpc::get_token_balance(&sdk.client, &native_token, &address, None)
+                .await
+                .map_err(|e| format!("Query balance error: {}", e))?,
+        );
+    }
+
+    let masp_address = sdk
+        .wallet()
+        .await
+        .find_address("masp")
+        .unwrap()
+        .into_owned();
+    let mut tokens = Vec::new();
+    for token in registry.ibc_tokens() {
+        tokens.push(TokenSnapshot {
+            symbol: token.symbol.clone(),
+            decimals: token.decimals,
+            total_supply: rpc::get_token_total_supply(&sdk.client, &token.address)
+                .await
+                .map_err(|e| format!("Query total supply error: {}", e))?,
+            masp_balance: rpc::get_token_balance(&sdk.client, &token.address, &masp_address, None)
+                .await
+                .map_err(|e| format!("Query MASP balance error: {}", e))?,
+        });
+    }
+
+    let validators = query_top_validators(sdk, book, epoch, num_vals)
+        .await?
+        .into_iter()
+        .enumerate()
+        .map(|(idx, val)| ValidatorSnapshot {
+            rank: idx as u32 + 1,
+            address: val.address.to_string(),
+            name: val.name,
+            stake: val.stake,
+        })
+        .collect();
+
+    let mut categories = Vec::new();
+    if genesis {
+        for category in get_genesis_categories() {
+            categories.push(CategorySnapshot {
+                category: category.name.to_string(),
+                genesis_balance: category.genesis_balance,
+                staked: query_stake(sdk, epoch, category.delegators(book)).await?,
+            });
+        }
+        categories.push(CategorySnapshot {
+            category: String::from("Public allocations"),
+            genesis_balance: get_public_alloc_balance(),
+            staked: get_public_alloc_stake(total_staked, categories.iter().map(|c| c.staked)),
+        });
+    }
+
+    Ok(Snapshot {
+        height: last_block.height.0,
+        epoch: epoch.0,
+        time: last_block.time.to_string(),
+        chain_id: chain_id.to_string(),
+        total_staked,
+        native_supply,
+        pgf_balance: balances[0],
+        gov_balance: balances[1],
+        staking_rewards_rate,
+        pos_inflation_rate: inflation_rate,
+        tokens,
+        validators,
+        categories,
+    })
+}
+
+fn amount(raw: String) -> token::Amount {
+    token::Amount::from_string_precise(&raw).expect("Invalid amount in the store")
+}
+
+fn dec(raw: String) -> Dec {
+    Dec::from_str(&raw).expect("Invalid decimal in the store")
+}
+
+/// SQLite database of snapshots, keyed by block height. Amounts are stored
+/// as raw integers and decimals as text so they read back exactly.
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    pub fn open(path: impl AsRef<Path>) -> Self {
+        let conn = Connection::open(path).expect("Could not open the snapshot database");
+        conn.execute_batch(SCHEMA)
+            .expect("Could not create the snapshot tables");
+        Self { conn }
+    }
+
+    /// Record a snapshot, replacing any earlier one at the same height
+    pub fn insert(&mut self, snapshot: &Snapshot) {
+        let tx = self.conn.transaction().unwrap();
+        let height = snapshot.height as i64;
+        for table in ["tokens", "validators", "categories", "snapshots"] {
+            tx.execute(
+                &format!("DELETE FROM {} WHERE height = ?1", table),
+                params![height],
+            )
+            .unwrap();
+        }
+        tx.execute(
+            "INSERT INTO snapshots VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                height,
+                snapshot.epoch as i64,
+                snapshot.time,
+                snapshot.chain_id,
+                snapshot.total_staked.to_string(),
+                snapshot.native_supply.to_string(),
+                snapshot.pgf_balance.to_string(),
+                snapshot.gov_balance.to_string(),
+                snapshot.staking_rewards_rate.to_string(),
+                snapshot.pos_inflation_rate.to_string(),
+            ],
+        )
+        .expect("Could not insert the snapshot");
+        for token in &snapshot.tokens {
+            tx.execute(
+                "INSERT INTO tokens VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    height,
+                    token.symbol,
+                    token.decimals,
+                    token.total_supply.to_string(),
+                    token.masp_balance.to_string(),
+                ],
+            )
+            .unwrap();
+        }
+        for val in &snapshot.validators {
+            tx.execute(
+                "INSERT INTO validators VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    height,
+                    val.rank,
+                    val.address,
+                    val.name,
+                    val.stake.to_string()
+                ],
+            )
+            .unwrap();
+        }
+        for category in &snapshot.categories {
+            tx.execute(
+                "INSERT INTO categories VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    height,
+                    category.category,
+                    category.genesis_balance.to_string(),
+                    category.staked.to_string(),
+                ],
+            )
+            .unwrap();
+        }
+        tx.commit().expect("Could not commit the snapshot");
+    }
+
+    /// Snapshots with an epoch in `from..=to`, oldest first
+    pub fn snapshots(&self, from: Option<u64>, to: Option<u64>) -> Vec<Snapshot> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT height, epoch, time, chain_id, total_staked, native_supply, pgf_balance,
+                    gov_balance, staking_rewards_rate, pos_inflation_rate
+                 FROM snapshots WHERE epoch >= ?1 AND epoch <= ?2 ORDER BY height",
+            )
+            .unwrap();
+        let rows = stmt
+            .query_map(
+                params![
+                    from.unwrap_or(0) as i64,
+                    to.unwrap_or(i64::MAX as u64) as i64
+                ],
+                |row| {
+                    Ok(Snapshot {
+                        height: row.get::<_, i64>(0)? as u64,
+                        epoch: row.get::<_, i64>(1)? as u64,
+                        time: row.get(2)?,
+                        chain_id: row.get(3)?,
+                        total_staked: amount(row.get(4)?),
+                        native_supply: amount(row.get(5)?),
+                        pgf_balance: amount(row.get(6)?),
+                        gov_balance: amount(row.get(7)?),
+                        staking_rewards_rate: dec(row.get(8)?),
+                        pos_inflation_rate: dec(row.get(9)?),
+                        tokens: vec![],
+                        validators: vec![],
+                        categories: vec![],
+                    })
+                },
+            )
+            .unwrap();
+
+        let mut snapshots = Vec::new();
+        for snapshot in rows {
+            let mut snapshot = snapshot.expect("Could not read a snapshot");
+            let height = snapshot.height as i64;
+            snapshot.tokens = self
+                .conn
+                .prepare("SELECT symbol, decimals, total_supply, masp_balance FROM tokens WHERE height = ?1 ORDER BY symbol")
+                .unwrap()
+                .query_map(params![height], |row| {
+                    Ok(TokenSnapshot {
+                        symbol: row.get(0)?,
+                        decimals: row.get(1)?,
+                        total_supply: amount(row.get(2)?),
+                        masp_balance: amount(row.get(3)?),
+                    })
+                })
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+            snapshot.validators = self
+                .conn
+                .prepare("SELECT rank, address, name, stake FROM validators WHERE height = ?1 ORDER BY rank")
+                .unwrap()
+                .query_map(params![height], |row| {
+                    Ok(ValidatorSnapshot {
+                        rank: row.get(0)?,
+                        address: row.get(1)?,
+                        name: row.get(2)?,
+                        stake: amount(row.get(3)?),
+                    })
+                })
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+            snapshot.categories = self
+                .conn
+                .prepare("SELECT category, genesis_balance, staked FROM categories WHERE height = ?1 ORDER BY rowid")
+                .unwrap()
+                .query_map(params![height], |row| {
+                    Ok(CategorySnapshot {
+                        category: row.get(0)?,
+                        genesis_balance: amount(row.get(1)?),
+                        staked: amount(row.get(2)?),
+                    })
+                })
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+            snapshots.push(snapshot);
+        }
+        snapshots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(height: u64, epoch: u64, staked: u64) -> Snapshot {
+        Snapshot {
+            height,
+            epoch,
+            time: String::from("2025-01-01T00:00:00Z"),
+            chain_id: String::from("namada.5f5de2dd1b88cba30586420"),
+            total_staked: token::Amount::native_whole(staked),
+            native_supply: token::Amount::native_whole(1_000_000_000),
+            pgf_balance: token::Amount::from(123_456_789),
+            gov_balance: token::Amount::zero(),
+            staking_rewards_rate: Dec::new(12, 2).unwrap(),
+            pos_inflation_rate: Dec::new(5, 2).unwrap(),
+            tokens: vec![TokenSnapshot {
+                symbol: String::from("OSMO"),
+                decimals: 6,
+                total_supply: token::Amount::from(1_000),
+                masp_balance: token::Amount::from(250),
+            }],
+            validators: vec![ValidatorSnapshot {
+                rank: 1,
+                address: String::from("tnam1qyctcwkgthr06k7lx38zmjka5dakmvhhyyr0zafu"),
+                name: None,
+                stake: token::Amount::native_whole(staked / 10),
+            }],
+            categories: vec![CategorySnapshot {
+                category: String::from("Backers"),
+                genesis_balance: token::Amount::native_whole(320_364_605),
+                staked: token::Amount::native_whole(staked / 2),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_store_roundtrip() {
+        let mut store = Store::open(":memory:");
+        store.insert(&snapshot(200, 2, 400_000_000));
+        store.insert(&snapshot(100, 1, 300_000_000));
+        // Same height again replaces the earlier snapshot
+        store.insert(&snapshot(200, 2, 450_000_000));
+
+        let snapshots = store.snapshots(None, None);
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(snapshots[0], snapshot(100, 1, 300_000_000));
+        assert_eq!(snapshots[1], snapshot(200, 2, 450_000_000));
+        assert_eq!(snapshots[1].staked_ratio(), Dec::new(45, 2).unwrap());
+
+        assert_eq!(store.snapshots(Some(2), None).len(), 1);
+        assert!(store.snapshots(Some(3), None).is_empty());
+    }
+}
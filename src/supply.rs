@@ -0,0 +1,174 @@
+use namada_core::chain::Epoch;
+use namada_core::token;
+use namada_proof_of_stake::parameters::OwnedPosParams;
+use namada_proof_of_stake::rewards::compute_inflation;
+use namada_proof_of_stake::storage_key::{last_pos_inflation_amount_key, last_staked_ratio_key};
+use namada_sdk::io::NullIo;
+use namada_sdk::masp::fs::FsShieldedUtils;
+use namada_sdk::tendermint_rpc::HttpClient;
+use namada_sdk::wallet::fs::FsWalletUtils;
+use namada_sdk::{rpc, Namada, NamadaImpl};
+use namada_token::Dec;
+
+use crate::pgf::{query_pgf_state, PgfState};
+
+/// Inputs of the PoS and PGF inflation, as of the current epoch
+pub struct InflationModel {
+    pub pos_params: OwnedPosParams,
+    /// PoS inflation minted in the last epoch
+    pub last_inflation: token::Amount,
+    pub last_staked_ratio: Dec,
+    pub pgf: PgfState,
+    pub pgf_balance: token::Amount,
+}
+
+/// Projected state at the end of an epoch
+#[derive(Debug, Clone)]
+pub struct SupplyPoint {
+    pub epoch: Epoch,
+    /// Effective native supply, which excludes the PGF balance
+    pub supply: token::Amount,
+    pub staked: token::Amount,
+    pub staked_ratio: Dec,
+    /// PoS inflation minted in this epoch
+    pub pos_inflation: token::Amount,
+    /// Annualized PoS inflation rate
+    pub inflation_rate: Dec,
+    /// Annualized staking rewards rate, before validator commissions
+    pub staking_apr: Dec,
+    pub pgf_balance: token::Amount,
+}
+
+pub async fn query_inflation_model(
+    sdk: &NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+) -> InflationModel {
+    let pos_params = rpc::get_pos_params(&sdk.client)
+        .await
+        .expect("Query PoS parameters error")
+        .owned;
+    let last_inflation: token::Amount =
+        rpc::query_storage_value(&sdk.client, &last_pos_inflation_amount_key())
+            .await
+            .expect("Query last PoS inflation error");
+    let last_staked_ratio: Dec = rpc::query_storage_value(&sdk.client, &last_staked_ratio_key())
+        .await
+        .expect("Query last staked ratio error");
+    let pgf = query_pgf_state(sdk).await;
+    let pgf_address = sdk.wallet().await.find_address("pgf").unwrap().into_owned();
+    let pgf_balance = rpc::get_token_balance(&sdk.client, &sdk.native_token(), &pgf_address, None)
+        .await
+        .expect("Query PGF balance error");
+
+    InflationModel {
+        pos_params,
+        last_inflation,
+        last_staked_ratio,
+        pgf,
+        pgf_balance,
+    }
+}
+
+impl InflationModel {
+    /// Simulate the next `epochs` epochs with the given share of the supply
+    /// staked. The PoS inflation follows the protocol's PD controller and the
+    /// PGF side of each epoch is [`PgfState::step_epoch`].
+    pub fn project(&self, from_epoch: Epoch, epochs: u64, staked_ratio: Dec) -> Vec<SupplyPoint> {
+        let epochs_per_year = self.pgf.epochs_per_year;
+        let mut supply = self.pgf.native_supply;
+        let mut pgf_balance = self.pgf_balance;
+        let mut last_inflation = self.last_inflation;
+        let mut last_staked_ratio = self.last_staked_ratio;
+        let mut points = Vec::new();
+
+        for epoch in 1..=epochs {
+            let staked = supply.mul_floor(staked_ratio).unwrap();
+            let pos_inflation = compute_inflation(
+                staked,
+                supply,
+                self.pos_params.max_inflation_rate,
+                last_inflation,
+                self.pos_params.rewards_gain_p,
+                self.pos_params.rewards_gain_d,
+                epochs_per_year,
+                self.pos_params.target_staked_ratio,
+                last_staked_ratio,
+            )
+            .expect("PoS inflation computation error");
+
+            let annual_inflation = Dec::try_from(pos_inflation)
+                .unwrap()
+                .checked_mul(Dec::from(epochs_per_year))
+                .unwrap();
+            let inflation_rate = annual_inflation
+                .checked_div(Dec::try_from(supply).unwrap())
+                .unwrap_or_default();
+            let staking_apr = annual_inflation
+                .checked_div(Dec::try_from(staked).unwrap())
+                .unwrap_or_default();
+
+            (supply, pgf_balance) = self.pgf.step_epoch(supply, pgf_balance, pos_inflation);
+            last_inflation = pos_inflation;
+            last_staked_ratio = staked_ratio;
+
+            points.push(SupplyPoint {
+                epoch: Epoch(from_epoch.0 + epoch),
+                supply,
+                staked,
+                staked_ratio,
+                pos_inflation,
+                inflation_rate,
+                staking_apr,
+                pgf_balance,
+            });
+        }
+        points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use namada_governance::pgf::parameters::PgfParameters;
+
+    use super::*;
+
+    fn model(last_staked_ratio: Dec) -> InflationModel {
+        InflationModel {
+            pos_params: OwnedPosParams::default(),
+            last_inflation: token::Amount::zero(),
+            last_staked_ratio,
+            pgf: PgfState {
+                parameters: PgfParameters {
+                    pgf_inflation_rate: Dec::zero(),
+                    stewards_inflation_rate: Dec::zero(),
+                    ..PgfParameters::default()
+                },
+                stewards: vec![],
+                fundings: vec![],
+                epochs_per_year: 365,
+                native_supply: token::Amount::native_whole(1_000_000_000),
+                pos_inflation_rate: Dec::zero(),
+            },
+            pgf_balance: token::Amount::zero(),
+        }
+    }
+
+    #[test]
+    fn test_project_supply() {
+        let low = Dec::new(3, 1).unwrap();
+        let high = Dec::new(9, 1).unwrap();
+
+        let below_target = model(low).project(Epoch(0), 30, low);
+        let above_target = model(high).project(Epoch(0), 30, high);
+
+        // The controller raises inflation while the staked ratio is below the
+        // target and keeps it off above it
+        let last = below_target.last().unwrap();
+        assert!(last.pos_inflation > below_target[0].pos_inflation);
+        assert!(last.supply > token::Amount::native_whole(1_000_000_000));
+        assert!(last.staking_apr > last.inflation_rate);
+        assert!(last.inflation_rate <= OwnedPosParams::default().max_inflation_rate);
+        assert!(above_target
+            .iter()
+            .all(|point| point.pos_inflation.is_zero()));
+    }
+}
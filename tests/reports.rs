@@ -0,0 +1,491 @@
+//! Run the reports against the mock RPC server and check what they print
+
+use std::str::FromStr;
+
+use namada_core::address::{gen_deterministic_established_address, Address};
+use namada_core::chain::{BlockHeader, BlockHeight, Epoch};
+use namada_core::hash::Hash;
+use namada_core::masp::MaspEpoch;
+use namada_core::parameters::EpochDuration;
+use namada_core::time::DurationSecs;
+use namada_core::token;
+use namada_ibc::storage as ibc_storage;
+use namada_ibc::trace::ibc_token;
+use namada_proof_of_stake::rewards::PosRewardsRates;
+use namada_proof_of_stake::types::{
+    BondDetails, BondId, BondsAndUnbondsDetail, BondsAndUnbondsDetails, ValidatorMetaData,
+    WeightedValidator,
+};
+use namada_sdk::address::InternalAddress;
+use namada_sdk::queries::RPC;
+use namada_sdk::state::LastBlock;
+use namada_sdk::time::DateTimeUtc;
+use namada_sdk::wallet::fs::FsWalletUtils;
+use namada_token::storage_key::{
+    balance_key, masp_kd_gain_key, masp_kp_gain_key, masp_last_inflation_key,
+    masp_last_locked_amount_key, masp_locked_amount_target_key, masp_max_reward_rate_key,
+};
+use namada_token::Dec;
+use namada_utils::mock::{Fixtures, MockRpc};
+use namada_utils::{NAMADA_UTILS_DIR, RPC_ENV_VAR};
+use tempfile::TempDir;
+
+const CHAIN_ID: &str = "namada.5f5de2dd1b88cba30586420";
+const EPOCH: Epoch = Epoch(5);
+/// Unix time of the first block of the current epoch
+const EPOCH_START: i64 = 1_700_000_000;
+
+fn nam() -> Address {
+    gen_deterministic_established_address("nam")
+}
+
+fn validator(idx: u8) -> Address {
+    gen_deterministic_established_address(format!("validator-{}", idx))
+}
+
+fn time(unix: i64) -> DateTimeUtc {
+    DateTimeUtc::from_unix_timestamp(unix).unwrap()
+}
+
+fn header(unix: i64) -> Option<BlockHeader> {
+    Some(BlockHeader {
+        hash: Hash::default(),
+        time: time(unix),
+        next_validators_hash: Hash::default(),
+    })
+}
+
+/// Epoch 5 started at height 1000 and the chain is at height 1050, with
+/// 400M of 1B NAM staked
+fn chain_fixtures() -> Fixtures {
+    let shell = RPC.shell();
+    let pos = RPC.vp().pos();
+    let mut fixtures = Fixtures::default();
+    fixtures.insert(shell.native_token_path(), &nam());
+    fixtures.insert(shell.epoch_path(), &EPOCH);
+    fixtures.insert(
+        shell.last_block_path(),
+        &Some(LastBlock {
+            height: BlockHeight(1050),
+            time: time(EPOCH_START + 250),
+        }),
+    );
+    fixtures.insert(
+        pos.total_stake_path(&Some(EPOCH)),
+        &token::Amount::native_whole(400_000_000),
+    );
+    fixtures.insert(
+        RPC.vp().token().effective_native_supply_path(),
+        &token::Amount::native_whole(1_000_000_000),
+    );
+    fixtures
+}
+
+/// Working directory of a report: the config, a wallet that knows `nam`
+/// and empty MASP parameter files so that none are downloaded
+fn workdir(transparent_addresses: &[Address]) -> TempDir {
+    let dir = TempDir::new().unwrap();
+    let addresses = transparent_addresses
+        .iter()
+        .map(|address| format!("\"{}\"", address))
+        .collect::<Vec<_>>()
+        .join(", ");
+    std::fs::create_dir_all(dir.path().join("config")).unwrap();
+    std::fs::write(
+        dir.path().join("config/config.toml"),
+        format!(
+            "chain_id = \"{}\"\ntransparent_addresses = [{}]\n\n[[tokens]]\nsymbol = \"NAM\"\naddress = \"nam\"\ndecimals = 6\norigin_chain = \"{}\"\n",
+            CHAIN_ID, addresses, CHAIN_ID
+        ),
+    )
+    .unwrap();
+
+    let mut wallet = FsWalletUtils::new(dir.path().join("sdk-wallet"));
+    wallet.insert_address("nam", nam(), true);
+    wallet.save().unwrap();
+
+    let params_dir = dir.path().join("masp-params");
+    std::fs::create_dir_all(&params_dir).unwrap();
+    for name in [
+        "masp-spend.params",
+        "masp-output.params",
+        "masp-convert.params",
+    ] {
+        std::fs::write(params_dir.join(name), []).unwrap();
+    }
+    dir
+}
+
+/// Add a file, e.g. an address list, to the working directory
+fn write_file(dir: &TempDir, rel_path: &str, contents: &str) {
+    std::fs::write(dir.path().join(rel_path), contents).unwrap();
+}
+
+/// Add entries, e.g. more `[[tokens]]`, to the config of a working directory
+fn append_config(dir: &TempDir, contents: &str) {
+    let path = dir.path().join("config/config.toml");
+    let config = std::fs::read_to_string(&path).unwrap();
+    std::fs::write(path, config + contents).unwrap();
+}
+
+/// Run a report binary against the mock server and return its output
+async fn run(bin: &str, dir: &TempDir, rpc: &MockRpc, args: &[&str]) -> String {
+    let output = tokio::process::Command::new(bin)
+        .args(args)
+        .current_dir(dir.path())
+        .env(RPC_ENV_VAR, &rpc.url)
+        .env(NAMADA_UTILS_DIR, dir.path())
+        .env("NAMADA_MASP_PARAMS_DIR", dir.path().join("masp-params"))
+        .output()
+        .await
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    assert!(
+        output.status.success(),
+        "{} failed:\n{}\n{}",
+        bin,
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    stdout
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_get_top_validators() {
+    let pos = RPC.vp().pos();
+    let mut fixtures = chain_fixtures();
+    // 1.1M staked in total, of which the consensus set holds 1M
+    fixtures.insert(
+        pos.total_stake_path(&Some(EPOCH)),
+        &token::Amount::native_whole(1_100_000),
+    );
+    let stakes = [500_000, 300_000, 200_000];
+    fixtures.insert(
+        pos.consensus_validator_set_path(&Some(EPOCH)),
+        &stakes
+            .iter()
+            .enumerate()
+            .map(|(idx, stake)| WeightedValidator {
+                bonded_stake: token::Amount::native_whole(*stake),
+                address: validator(idx as u8),
+            })
+            .collect::<std::collections::BTreeSet<_>>(),
+    );
+    for (idx, name) in [Some("Alpha"), Some("Beta"), None].into_iter().enumerate() {
+        let address = validator(idx as u8);
+        fixtures.insert(
+            pos.validator_metadata_path(&address),
+            &name.map(|name| ValidatorMetaData {
+                email: String::from("ops@example.com"),
+                description: None,
+                website: None,
+                discord_handle: None,
+                avatar: None,
+                name: Some(name.to_string()),
+            }),
+        );
+        fixtures.insert(
+            pos.validator_commission_path(&address, &Some(EPOCH)),
+            &namada_proof_of_stake::types::CommissionPair {
+                commission_rate: Some(Dec::new(5, 2).unwrap()),
+                max_commission_change_per_epoch: Some(Dec::new(1, 2).unwrap()),
+                epoch: EPOCH,
+            },
+        );
+    }
+
+    let rpc = MockRpc::start(fixtures).await;
+    let dir = workdir(&[]);
+    let output = run(env!("CARGO_BIN_EXE_get_top_validators"), &dir, &rpc, &[]).await;
+
+    assert!(output.contains("Current epoch: 5"));
+    assert!(output.contains("45.45% (45.45%) --- Alpha"));
+    assert!(output.contains("27.27% (72.72%) --- Beta"));
+    assert!(output.contains(&format!("18.18% (90.90%) --- {}", validator(2))));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_chain_info() {
+    let shell = RPC.shell();
+    let mut fixtures = chain_fixtures();
+    fixtures.insert(
+        shell.first_block_height_of_current_epoch_path(),
+        &BlockHeight(1000),
+    );
+    fixtures.insert_storage(
+        &namada_parameters::storage::get_epoch_duration_storage_key(),
+        &EpochDuration {
+            min_num_of_blocks: 100,
+            min_duration: DurationSecs(600),
+        },
+    );
+    // A block every 5s
+    for (height, unix) in [
+        (1000, EPOCH_START),
+        (1048, EPOCH_START + 240),
+        (1049, EPOCH_START + 245),
+        (1050, EPOCH_START + 250),
+    ] {
+        fixtures.insert(shell.block_header_path(&BlockHeight(height)), &header(unix));
+    }
+    fixtures.insert(
+        RPC.vp().token().staking_rewards_rate_path(),
+        &PosRewardsRates {
+            staking_rewards_rate: Dec::new(12, 2).unwrap(),
+            inflation_rate: Dec::new(5, 2).unwrap(),
+        },
+    );
+    fixtures.insert(
+        RPC.vp()
+            .pos()
+            .total_stake_path(&Some(EPOCH.checked_add(2).unwrap())),
+        &token::Amount::native_whole(410_000_000),
+    );
+    fixtures.insert_storage(
+        &balance_key(&nam(), &Address::Internal(InternalAddress::Pgf)),
+        &token::Amount::native_whole(1234),
+    );
+
+    // The governance account has no balance key
+    let rpc = MockRpc::start(fixtures.with_empty_storage()).await;
+    let dir = workdir(&[]);
+    let output = run(
+        env!("CARGO_BIN_EXE_chain_info"),
+        &dir,
+        &rpc,
+        &["--sample-blocks", "2"],
+    )
+    .await;
+
+    assert!(output.contains("Last block height: 1050"));
+    assert!(output.contains("Current epoch: 5"));
+    assert!(output.contains("First block height of epoch 5: 1000."));
+    assert!(output.contains("Average block time over the last 2 blocks: 5.00s"));
+    // 600s after the epoch start at 5s per block, plus the switch delay
+    assert!(output.contains("Epoch 6 begins at block height 1122"));
+    assert!(output.contains("PGF balance: 1234.000000 NAM"));
+    assert!(output.contains("Gov balance: 0.000000 NAM"));
+    assert!(output.contains("Staked ratio: 40%"));
+    assert!(output.contains("Total stake in 2 epochs: 410000000.000000"));
+}
+
+/// The bonds of a delegator, all active since epoch 1, in whole NAM
+fn insert_bonds(fixtures: &mut Fixtures, delegator: &Address, bonds: &[(Address, u64)]) {
+    let mut details = BondsAndUnbondsDetails::default();
+    for (validator, amount) in bonds {
+        details.insert(
+            BondId {
+                source: delegator.clone(),
+                validator: validator.clone(),
+            },
+            BondsAndUnbondsDetail {
+                bonds: vec![BondDetails {
+                    start: Epoch(1),
+                    amount: token::Amount::native_whole(*amount),
+                    slashed_amount: None,
+                }],
+                unbonds: vec![],
+                slashes: vec![],
+            },
+        );
+    }
+    fixtures.insert(
+        RPC.vp()
+            .pos()
+            .bonds_and_unbonds_path(&Some(delegator.clone()), &None),
+        &details,
+    );
+}
+
+/// A delegator with 250 NAM, 1000 NAM bonded and 12 NAM of rewards
+fn delegator_fixtures(delegator: &Address) -> Fixtures {
+    let mut fixtures = chain_fixtures();
+    fixtures.insert_storage(
+        &balance_key(&nam(), delegator),
+        &token::Amount::native_whole(250),
+    );
+    insert_bonds(&mut fixtures, delegator, &[(validator(0), 1000)]);
+    fixtures.insert(
+        RPC.vp()
+            .pos()
+            .rewards_path(&validator(0), &Some(delegator.clone()), &None),
+        &token::Amount::native_whole(12),
+    );
+    fixtures
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_print_balances() {
+    let delegator = gen_deterministic_established_address("delegator");
+    let rpc = MockRpc::start(delegator_fixtures(&delegator)).await;
+    let dir = workdir(&[delegator.clone()]);
+    let output = run(env!("CARGO_BIN_EXE_print_balances"), &dir, &rpc, &[]).await;
+
+    assert!(output.contains(&format!("{}:", delegator)));
+    assert!(output.contains("Balance: 250 NAM"));
+    assert!(output.contains("Bonded: 1000.000000 NAM"));
+    assert!(output.contains("Unclaimed rewards: 12.000000 NAM"));
+    assert!(output.contains("Total transparent tokens to name: 1262.000000 NAM"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_record_and_replay() {
+    let delegator = gen_deterministic_established_address("delegator");
+    let rpc = MockRpc::start(delegator_fixtures(&delegator)).await;
+    let dir = workdir(&[delegator.clone()]);
+    let recording = dir.path().join("recording");
+    let recording = recording.to_str().unwrap();
+    let bin = env!("CARGO_BIN_EXE_print_balances");
+    let recorded = run(bin, &dir, &rpc, &["--record", recording]).await;
+
+    // The node is gone: every query must come from the recording
+    let offline = MockRpc::start(Fixtures::default()).await;
+    let replayed = run(bin, &dir, &offline, &["--replay", recording]).await;
+    assert_eq!(recorded, replayed);
+    assert!(replayed.contains("Total transparent tokens to name: 1262.000000 NAM"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_genesis_staking() {
+    let backer = gen_deterministic_established_address("backer");
+    let core = gen_deterministic_established_address("core");
+    let rd = gen_deterministic_established_address("rd");
+    let future = gen_deterministic_established_address("future");
+    let chorus_one = Address::from_str("tnam1qxsx2ezu89gx252kwwluqp7hadyp285tkczhaqg0").unwrap();
+
+    let mut fixtures = chain_fixtures();
+    insert_bonds(&mut fixtures, &backer, &[(chorus_one, 1000)]);
+    insert_bonds(&mut fixtures, &core, &[(validator(0), 2000)]);
+    insert_bonds(&mut fixtures, &rd, &[(validator(0), 500)]);
+    insert_bonds(&mut fixtures, &future, &[]);
+
+    let rpc = MockRpc::start(fixtures).await;
+    let dir = workdir(&[]);
+    write_file(&dir, "config/backers.txt", &format!("{}\n", backer));
+    write_file(&dir, "config/core_team.txt", &format!("{}\n", core));
+    // The R&D record is named after its address book label
+    write_file(
+        &dir,
+        "config/rd_ecosystem_dev.json",
+        &format!(
+            r#"[{{ "address": "{}", "amount": "5000000000", "category": "rd", "name": "" }}]"#,
+            rd
+        ),
+    );
+    write_file(
+        &dir,
+        "config/public_allocations_future.json",
+        &format!(
+            r#"[{{ "address": "{}", "amount": "100000000", "category": "future", "name": "Future" }}]"#,
+            future
+        ),
+    );
+    write_file(
+        &dir,
+        "config/address_book.toml",
+        &format!(
+            "[[addresses]]\naddress = \"{}\"\nlabel = \"Backer Fund\"\n\n[[addresses]]\naddress = \"{}\"\nlabel = \"Ecosystem Fund\"\n",
+            backer, rd
+        ),
+    );
+    let output = run(env!("CARGO_BIN_EXE_genesis_staking"), &dir, &rpc, &[]).await;
+
+    assert!(output.contains("Total stake: 400000000.000000 NAM"));
+    assert!(output.contains("Backer fraction of total stake: 0.00025%"));
+    assert!(output.contains("Fraction of backer stake held by top 5 validators: 100%"));
+    assert!(output.contains("  --> Chorus One: 100%"));
+    assert!(output.contains("Fraction of core team stake held by top 5 validators: 0%"));
+    assert!(output.contains("  --> Ecosystem Fund: 5000.000000"));
+    assert!(output.contains("Fraction of balance staked: 10%"));
+    assert!(output.contains("Fraction of total R&D stake: 100%"));
+    assert!(output.contains("  --> Future: 100.000000"));
+
+    let csv = std::fs::read_to_string(dir.path().join("output.csv")).unwrap();
+    assert!(csv.contains(&format!("{},Backer Fund,1000.000000,0,0,1,0,0", backer)));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_token_metrics() {
+    let osmo_trace = "transfer/channel-0/uosmo";
+    let osmo = ibc_token(osmo_trace);
+    let masp = Address::Internal(InternalAddress::Masp);
+    let uosmo = |whole: u64| token::Amount::from(whole * 1_000_000);
+
+    let mut fixtures = chain_fixtures();
+    let token_rpc = RPC.vp().token();
+    for (address, supply, shielded) in [
+        (
+            nam(),
+            token::Amount::native_whole(1_000_000_000),
+            token::Amount::native_whole(100_000_000),
+        ),
+        (osmo.clone(), uosmo(5000), uosmo(1250)),
+    ] {
+        fixtures.insert(token_rpc.total_supply_path(&address), &supply);
+        fixtures.insert_storage(&balance_key(&address, &masp), &shielded);
+    }
+    fixtures.insert_storage(&ibc_storage::mint_limit_key(&osmo), &uosmo(10_000));
+    fixtures.insert_storage(&ibc_storage::throughput_limit_key(&osmo), &uosmo(1000));
+    fixtures.insert_storage(&ibc_storage::mint_amount_key(&osmo), &uosmo(5000));
+    fixtures.insert_storage(&ibc_storage::deposit_key(&osmo), &uosmo(900));
+
+    // 730 epochs a year, 2 per MASP epoch
+    fixtures.insert_storage(
+        &namada_parameters::storage::get_epochs_per_year_key(),
+        &730u64,
+    );
+    fixtures.insert_storage(
+        &namada_parameters::storage::get_masp_epoch_multiplier_key(),
+        &2u64,
+    );
+    fixtures.insert(RPC.shell().masp_epoch_path(), &MaspEpoch::new(3));
+    // Only NAM has shielded rewards
+    fixtures.insert_storage(&masp_max_reward_rate_key(&nam()), &Dec::new(1, 1).unwrap());
+    fixtures.insert_storage(&masp_kp_gain_key(&nam()), &Dec::new(25, 2).unwrap());
+    fixtures.insert_storage(&masp_kd_gain_key(&nam()), &Dec::new(25, 2).unwrap());
+    fixtures.insert_storage(
+        &masp_locked_amount_target_key(&nam()),
+        &token::Amount::native_whole(50_000_000),
+    );
+    fixtures.insert_storage(
+        &masp_last_locked_amount_key(&nam()),
+        &token::Amount::native_whole(100_000_000),
+    );
+    fixtures.insert_storage(
+        &masp_last_inflation_key(&nam()),
+        &token::Amount::native_whole(10_000),
+    );
+    fixtures.insert(
+        token_rpc.denomination_path(&nam()),
+        &Some(token::Denomination(6)),
+    );
+    // No conversions yet: an empty map
+    fixtures.insert(RPC.shell().read_conversions_path(), &0u32);
+
+    // The OSMO withdrawals and shielded rewards have no keys
+    let rpc = MockRpc::start(fixtures.with_empty_storage()).await;
+    let dir = workdir(&[]);
+    append_config(
+        &dir,
+        &format!(
+            "\n[[tokens]]\nsymbol = \"OSMO\"\ntrace = \"{}\"\ndecimals = 6\norigin_chain = \"osmosis-1\"\n",
+            osmo_trace
+        ),
+    );
+    let output = run(env!("CARGO_BIN_EXE_token_metrics"), &dir, &rpc, &[]).await;
+
+    assert!(output.contains("OSMO: transfer/channel-0/uosmo (from osmosis-1)"));
+    assert!(output.contains("OSMO: 5000\n"));
+    assert!(output.contains("OSMO: 1250\n"));
+    assert!(output.contains("  Minted: 5000 of 10000 (50%), 5000 left"));
+    assert!(output.contains("  Throughput this epoch: 900 of 1000 (90%), 100 left"));
+    assert!(output.contains("WARNING: at or above 80% of an IBC limit: OSMO"));
+    assert!(output.contains("NAM: 10%"));
+    assert!(output.contains("OSMO: 25%"));
+    assert!(output.contains("MASP epoch: 3 (365 MASP epochs per year)"));
+    assert!(output.contains("  Max reward rate: 10%"));
+    assert!(output.contains("  Target locked amount: 50000000"));
+    assert!(output.contains("  Current shielded reward rate: 3.65%"));
+    assert!(output.contains("OSMO: no shielded rewards"));
+    assert!(output.contains("NAM: no conversions"));
+}